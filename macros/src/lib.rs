@@ -0,0 +1,283 @@
+//! `#[derive(Parse)]`: generates `impl Parse<'src> for T` from field and
+//! variant attributes, so most AST nodes in `areole::ast` no longer need a
+//! hand-written `Parse` impl. Modeled on PSPP's `FromTokens` derive.
+//!
+//! A struct's fields are parsed in declaration order. `#[token(Kind::X)]`
+//! requires a literal token (the field must be `Token<'src>`);
+//! `#[parse(delimited(Open, Close))]` parses an opening token, the field's
+//! inner value, then a closing token (the field must be
+//! `Delimited<Token<'src>, _, Token<'src>>`); `#[parse(separated(Sep))]`
+//! parses a non-empty `Sep`-separated list (the field must be
+//! `Separated<_, Sep, _>`). A field with no attribute just parses its own
+//! type.
+//!
+//! An enum tries each variant in turn, committing to the first whose
+//! leading token matches (a variant's own `#[token(Kind::X)]` checks for
+//! that literal token; otherwise its single field's `Peek` impl is used).
+//! If none match, the accumulated `Lookahead` reports the expected set.
+//!
+//! This crate has to live outside `areole` itself: `proc-macro = true`
+//! crates can't also export ordinary items, so the derive can't be added
+//! to the main crate in place.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parenthesized, parse_macro_input, Data, DeriveInput, Fields, Ident, Path, Token, Type};
+
+#[proc_macro_derive(Parse, attributes(token, parse))]
+pub fn derive_parse(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    let body = match &input.data {
+        Data::Struct(data) => derive_struct(&input, data),
+        Data::Enum(data) => derive_enum(&input, data),
+        Data::Union(_) => Err(syn::Error::new_spanned(
+            &input,
+            "`#[derive(Parse)]` does not support unions",
+        )),
+    };
+
+    let body = match body {
+        Ok(body) => body,
+        Err(e) => return e.to_compile_error().into(),
+    };
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = split_generics(&input);
+
+    let expanded = quote! {
+        impl #impl_generics crate::parser::Parse<'src> for #name #ty_generics #where_clause {
+            fn parse(
+                tokens: &mut ::std::iter::Peekable<crate::token::TokenIter<'src>>,
+            ) -> crate::parser::ParseResult<'src, Self> {
+                #body
+            }
+        }
+    };
+
+    expanded.into()
+}
+
+/// Every derived type either already has a `'src` lifetime parameter (the
+/// common case for AST nodes that borrow from source) or has none at all
+/// (e.g. `LitInt`); in the latter case `'src` is introduced fresh just for
+/// the `impl` block.
+fn split_generics(input: &DeriveInput) -> (TokenStream2, TokenStream2, Option<TokenStream2>) {
+    let (_, ty_generics, where_clause) = input.generics.split_for_impl();
+    let ty_generics = quote!(#ty_generics);
+    let where_clause = where_clause.map(|w| quote!(#w));
+
+    if input.generics.lifetimes().next().is_some() {
+        let params = &input.generics.params;
+        (quote!(<#params>), ty_generics, where_clause)
+    } else {
+        (quote!(<'src>), ty_generics, where_clause)
+    }
+}
+
+/// What a single field's (or variant's) `#[token]`/`#[parse]` attribute
+/// asks for.
+enum FieldMode {
+    /// No attribute: parse the field's own type.
+    Plain,
+    /// `#[token(Kind::X)]`: require a literal token of that kind.
+    Token(Path),
+    /// `#[parse(delimited(Open, Close))]`: open token, inner value, close
+    /// token. `Open`/`Close` are bare `Kind` variant names.
+    Delimited(Ident, Ident),
+    /// `#[parse(separated(Sep))]`: a non-empty `Sep`-separated list.
+    Separated,
+}
+
+fn field_mode(attrs: &[syn::Attribute]) -> syn::Result<FieldMode> {
+    for attr in attrs {
+        if attr.path().is_ident("token") {
+            let path: Path = attr.parse_args()?;
+            return Ok(FieldMode::Token(path));
+        }
+
+        if attr.path().is_ident("parse") {
+            return attr.parse_args_with(|input: syn::parse::ParseStream| {
+                let kind: Ident = input.parse()?;
+
+                if kind == "delimited" {
+                    let content;
+                    parenthesized!(content in input);
+                    let open: Ident = content.parse()?;
+                    content.parse::<Token![,]>()?;
+                    let close: Ident = content.parse()?;
+                    Ok(FieldMode::Delimited(open, close))
+                } else if kind == "separated" {
+                    let content;
+                    parenthesized!(content in input);
+                    let _sep: Ident = content.parse()?;
+                    Ok(FieldMode::Separated)
+                } else {
+                    Err(syn::Error::new_spanned(
+                        kind,
+                        "expected `delimited(..)` or `separated(..)`",
+                    ))
+                }
+            });
+        }
+    }
+
+    Ok(FieldMode::Plain)
+}
+
+fn extract_token(kind: &Path) -> TokenStream2 {
+    quote! {
+        match tokens.next() {
+            Some(Ok(t @ crate::token::Token { kind: #kind, .. })) => t,
+            Some(Ok(tok)) => return Err(crate::parser::ParseError::Unexpected {
+                found: tok,
+                expected: crate::parser::ExpectedSet::of_kind(#kind),
+            }),
+            Some(Err(e)) => return Err(crate::parser::ParseError::LexError(e)),
+            None => return Err(crate::parser::ParseError::Eof),
+        }
+    }
+}
+
+/// Pulls the middle type argument `T` out of a field declared as
+/// `Delimited<Opn, T, Cls>`, so the generated code knows what to parse
+/// between the open and close tokens.
+fn delimited_inner_ty(ty: &Type) -> syn::Result<Type> {
+    let Type::Path(path) = ty else {
+        return Err(syn::Error::new_spanned(
+            ty,
+            "`#[parse(delimited(..))]` requires a `Delimited<Opn, T, Cls>` field",
+        ));
+    };
+
+    let segment = path.path.segments.last().ok_or_else(|| {
+        syn::Error::new_spanned(ty, "`#[parse(delimited(..))]` requires a named type")
+    })?;
+
+    let syn::PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return Err(syn::Error::new_spanned(
+            ty,
+            "`#[parse(delimited(..))]` requires a `Delimited<Opn, T, Cls>` field",
+        ));
+    };
+
+    match args.args.iter().nth(1) {
+        Some(syn::GenericArgument::Type(t)) => Ok(t.clone()),
+        _ => Err(syn::Error::new_spanned(
+            ty,
+            "`#[parse(delimited(..))]` requires a `Delimited<Opn, T, Cls>` field",
+        )),
+    }
+}
+
+fn derive_struct(input: &DeriveInput, data: &syn::DataStruct) -> syn::Result<TokenStream2> {
+    let fields = match &data.fields {
+        Fields::Named(f) => &f.named,
+        Fields::Unnamed(f) => &f.unnamed,
+        Fields::Unit => {
+            return Err(syn::Error::new_spanned(
+                input,
+                "`#[derive(Parse)]` does not support unit structs",
+            ));
+        }
+    };
+
+    let mut bindings = Vec::new();
+    let mut idents = Vec::new();
+
+    for (i, field) in fields.iter().enumerate() {
+        let binding = Ident::new(&format!("__field_{i}"), proc_macro2::Span::call_site());
+        let mode = field_mode(&field.attrs)?;
+        let field_ty = &field.ty;
+
+        let value = match mode {
+            FieldMode::Plain => {
+                quote! { <#field_ty as crate::parser::Parse<'src>>::parse(tokens)? }
+            }
+            FieldMode::Token(kind) => extract_token(&kind),
+            FieldMode::Delimited(open, close) => {
+                let inner_ty = delimited_inner_ty(field_ty)?;
+                let open_tok = extract_token(&syn::parse_quote!(crate::token::Kind::#open));
+                let close_tok = extract_token(&syn::parse_quote!(crate::token::Kind::#close));
+                quote! {{
+                    let open = #open_tok;
+                    let inner = <#inner_ty as crate::parser::Parse<'src>>::parse(tokens)?;
+                    let close = #close_tok;
+                    crate::ast::Delimited { open, inner, close }
+                }}
+            }
+            FieldMode::Separated => {
+                quote! { <#field_ty>::parse_separated_nonempty(tokens)? }
+            }
+        };
+
+        bindings.push(quote! { let #binding = #value; });
+        idents.push(binding);
+    }
+
+    let construct = match &data.fields {
+        Fields::Named(f) => {
+            let names = f.named.iter().map(|field| field.ident.clone().unwrap());
+            quote! { Self { #(#names: #idents),* } }
+        }
+        Fields::Unnamed(_) => quote! { Self(#(#idents),*) },
+        Fields::Unit => unreachable!(),
+    };
+
+    Ok(quote! {
+        #(#bindings)*
+        Ok(#construct)
+    })
+}
+
+fn derive_enum(input: &DeriveInput, data: &syn::DataEnum) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+
+    let mut peeks = Vec::new();
+    let mut arms = Vec::new();
+
+    for variant in &data.variants {
+        let variant_ident = &variant.ident;
+        let field = match &variant.fields {
+            Fields::Unnamed(f) if f.unnamed.len() == 1 => &f.unnamed[0],
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    variant,
+                    "`#[derive(Parse)]` only supports enum variants with exactly one unnamed field",
+                ));
+            }
+        };
+        let field_ty = &field.ty;
+
+        let mode = field_mode(&variant.attrs)?;
+        let peek_cond = match mode {
+            FieldMode::Token(kind) => quote! {
+                matches!(tokens.peek(), Some(Ok(crate::token::Token { kind: #kind, .. })))
+            },
+            _ => quote! { <#field_ty as crate::parser::Peek<'src>>::peek(tokens) },
+        };
+
+        arms.push(quote! {
+            if #peek_cond {
+                return Ok(#name::#variant_ident(<#field_ty as crate::parser::Parse<'src>>::parse(tokens)?));
+            }
+        });
+
+        peeks.push(quote! { lookahead.peek::<#field_ty>(); });
+    }
+
+    Ok(quote! {
+        match tokens.peek() {
+            Some(Ok(_)) => {}
+            Some(Err(e)) => return Err(crate::parser::ParseError::LexError(e.clone())),
+            None => return Err(crate::parser::ParseError::Eof),
+        }
+
+        #(#arms)*
+
+        let mut lookahead = crate::parser::Lookahead::new(tokens);
+        #(#peeks)*
+        Err(lookahead.error())
+    })
+}
@@ -1,9 +1,11 @@
 use std::{borrow::Cow, iter::Peekable};
 
+use areole_macros::Parse;
+
 use crate::{
-    parser::{Parse, ParseError, ParseResult},
+    parser::{ExpectedSet, Lookahead, Parse, ParseError, ParseResult, Peek},
     span::{Span, Spanned},
-    token::{Kind, LexError, Token, TokenIter},
+    token::{Interned, Kind, LexError, Token, TokenIter},
 };
 
 macro_rules! extract_token {
@@ -18,10 +20,14 @@ macro_rules! extract_token {
                 t @ Token {
                     span: _,
                     kind: Kind::$ident,
+                    file: _,
                 },
             ) => t,
             )*
-            Ok(tok) => return Err(ParseError::InvalidToken(tok)),
+            Ok(tok) => return Err(ParseError::Unexpected {
+                found: tok,
+                expected: ExpectedSet::of_kinds([$(Kind::$ident),*]),
+            }),
             Err(e) => return Err(ParseError::LexError(e)),
         }
     }};
@@ -44,10 +50,14 @@ macro_rules! extract_token {
                     t @ Token {
                         span: _,
                         kind: Kind::$ident,
+                        file: _,
                     },
                 ) => t,
 
-                Ok(tok) => return Err(ParseError::InvalidToken(tok)),
+                Ok(tok) => return Err(ParseError::Unexpected {
+                    found: tok,
+                    expected: ExpectedSet::of_kind(Kind::$ident),
+                }),
                 Err(e) => return Err(ParseError::LexError(e)),
             })
         }
@@ -92,10 +102,100 @@ impl<'src> Spanned for Function<'src> {
     }
 }
 
+/// The span a `ParseError` itself covers, used to seed the placeholder
+/// `Stmt::Error` that `parse_recoverable` inserts for the statement that
+/// failed. `ParseError::Eof` carries no span of its own (the token stream is
+/// simply exhausted), so `fallback` — the position the failed statement
+/// started at — is used instead; that keeps the returned span's `end` from
+/// ever landing before `start`, which a bare `Span::new(0, 0)` could.
+fn error_span(err: &ParseError<'_>, fallback: usize) -> Span {
+    match err {
+        ParseError::LexError(e) => e.span(),
+        ParseError::Unexpected { found, .. } => found.span(),
+        ParseError::UnbalancedDelimiter { open, .. } => open.clone(),
+        ParseError::Eof => Span::new(fallback, fallback),
+    }
+}
+
+/// Advances `tokens` past whatever is left of a failed statement until the
+/// next synchronization point: a `/`, a bare `Ident` (a command without a
+/// leading slash), or a `#` comment. Thin wrapper around
+/// [`crate::parser::recover_to`] fixing the synchronizing kinds for
+/// statement-level recovery.
+fn synchronize<'src>(
+    tokens: &mut Peekable<TokenIter<'src>>,
+    already_reported: &Span,
+    errors: &mut Vec<ParseError<'src>>,
+) -> usize {
+    crate::parser::recover_to(
+        tokens,
+        &[Kind::Slash, Kind::Ident(Interned::default()), Kind::Comment("")],
+        already_reported,
+        errors,
+    )
+}
+
+impl<'src> Function<'src> {
+    /// Like [`Parse::parse`], but never aborts on the first error: each
+    /// statement that fails to parse is replaced with a `Stmt::Error`
+    /// covering it from its first token through the tokens skipped while
+    /// resynchronizing at the next command boundary, and parsing continues. Returns every error
+    /// encountered alongside the (partial) tree, so a caller such as an
+    /// LSP diagnostics pass can report all of them in one go.
+    pub fn parse_recoverable(
+        tokens: &mut Peekable<TokenIter<'src>>,
+    ) -> (Self, Vec<ParseError<'src>>) {
+        let mut statements = Vec::new();
+        let mut errors = Vec::new();
+
+        while let Some(next) = tokens.peek() {
+            let start = match next {
+                Ok(token) => token.span.start,
+                Err(err) => err.span().start,
+            };
+
+            match Stmt::parse(tokens) {
+                Ok(stmt) => statements.push(stmt),
+                Err(err) => {
+                    let err_span = error_span(&err, start);
+                    errors.push(err);
+                    let end = synchronize(tokens, &err_span, &mut errors);
+                    statements.push(Stmt::Error(Span::new(start, end)));
+                }
+            }
+        }
+
+        (Function::new(statements), errors)
+    }
+}
+
+#[test]
+fn recoverable_parse_eof_mid_statement_never_yields_an_inverted_span() {
+    use crate::span::FileRef;
+    use logos::Logos;
+
+    let src = "/say hi\n/";
+    let lex = Kind::lexer(src);
+    let mut tokens = TokenIter::new(lex, FileRef(0)).peekable();
+
+    let (function, errors) = Function::parse_recoverable(&mut tokens);
+
+    assert!(!errors.is_empty());
+    let Some(Stmt::Error(span)) = function.statements.last() else {
+        panic!("expected the unterminated trailing statement to be a Stmt::Error");
+    };
+    assert!(span.end >= span.start);
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Stmt<'src> {
     Command(StmtCommand<'src>),
     Comment(StmtComment<'src>),
+    /// A placeholder left by [`Function::parse_recoverable`] where a
+    /// statement failed to parse, covering that statement from its first
+    /// token through the tokens that were skipped to resynchronize. Carries
+    /// no data beyond its span.
+    Error(Span),
 }
 
 impl<'src> Spanned for Stmt<'src> {
@@ -103,6 +203,7 @@ impl<'src> Spanned for Stmt<'src> {
         match self {
             Stmt::Command(c) => c.span(),
             Stmt::Comment(c) => c.span(),
+            Stmt::Error(span) => span.clone(),
         }
     }
 }
@@ -113,6 +214,7 @@ impl<'src> Parse<'src> for Stmt<'src> {
             Some(Ok(Token {
                 kind: Kind::Comment(_),
                 span: _,
+                file: _,
             })) => Ok(Stmt::Comment(StmtComment::parse(tokens)?)),
 
             Some(Ok(_)) => Ok(Stmt::Command(StmtCommand::parse(tokens)?)),
@@ -125,16 +227,16 @@ impl<'src> Parse<'src> for Stmt<'src> {
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct StmtCommand<'src> {
-    slash: Option<Token<'src>>,
-    ident: Ident<'src>,
-    arguments: Option<Vec<Expr<'src>>>,
+    pub slash: Option<Token<'src>>,
+    pub ident: Ident<'src>,
+    pub arguments: Option<Separated<Expr<'src>, Token<'src>, false>>,
 }
 
 impl<'src> StmtCommand<'src> {
     pub fn new(
         slash: Option<Token<'src>>,
         ident: Ident<'src>,
-        arguments: Option<Vec<Expr<'src>>>,
+        arguments: Option<Separated<Expr<'src>, Token<'src>, false>>,
     ) -> Self {
         Self {
             slash,
@@ -171,17 +273,7 @@ impl<'src> Parse<'src> for StmtCommand<'src> {
             return Ok(StmtCommand::new(slash, ident, None));
         }
 
-        let mut arguments = vec![];
-
-        loop {
-            let expr = Expr::parse(tokens)?;
-
-            arguments.push(expr);
-
-            if tokens.peek().is_none() {
-                break;
-            }
-        }
+        let arguments = Separated::<Expr<'src>, Token<'src>, false>::parse_until(tokens, |_| false)?;
 
         Ok(StmtCommand::new(slash, ident, Some(arguments)))
     }
@@ -194,6 +286,8 @@ pub enum Expr<'src> {
     Range(ExprRange<'src>),
     Map(ExprMap<'src>),
     Target(ExprTarget<'src>),
+    Binary(ExprBinary<'src>),
+    Coord(ExprCoord<'src>),
 }
 
 impl<'src> Spanned for Expr<'src> {
@@ -204,22 +298,154 @@ impl<'src> Spanned for Expr<'src> {
             Expr::Range(r) => r.span(),
             Expr::Map(m) => m.span(),
             Expr::Target(t) => t.span(),
+            Expr::Binary(b) => b.span(),
+            Expr::Coord(c) => c.span(),
+        }
+    }
+}
+
+impl<'src> Expr<'src> {
+    /// Parses an expression using precedence climbing: an atom (the "nud"),
+    /// followed by as many binary operators as bind at least as tightly as
+    /// `min_bp`.
+    fn parse_bp(tokens: &mut Peekable<TokenIter<'src>>, min_bp: u8) -> ParseResult<'src, Self> {
+        let mut lhs = Self::parse_atom(tokens)?;
+
+        loop {
+            let op = match tokens.peek() {
+                Some(Ok(token)) => match operator_for(&token.kind) {
+                    Some(op) => op,
+                    None => break,
+                },
+                Some(Err(err)) => return Err(ParseError::LexError(err.clone())),
+                None => break,
+            };
+
+            let (left_bp, right_bp) = infix_bp(&op);
+            if left_bp < min_bp {
+                break;
+            }
+
+            let token = extract_token!(
+                tokens,
+                Kind::Equal
+                    | Kind::AddAssign
+                    | Kind::SubAssign
+                    | Kind::MulAssign
+                    | Kind::DivAssign
+                    | Kind::Gt
+                    | Kind::Lt
+                    | Kind::Wildcard
+            );
+            let op = ExprOperator::new(op, token.span());
+
+            let rhs = Self::parse_bp(tokens, right_bp)?;
+
+            lhs = Expr::Binary(ExprBinary::new(Box::new(lhs), op, Box::new(rhs)));
         }
+
+        Ok(lhs)
+    }
+
+    /// Parses a single prefix expression: a literal, a unary op (`!`/`§`), a
+    /// coordinate triple (starting with `~`/`^`), a `@` target, a range, or a map.
+    fn parse_atom(tokens: &mut Peekable<TokenIter<'src>>) -> ParseResult<'src, Self> {
+        let kind = match tokens.peek() {
+            Some(Ok(token)) => token.kind.clone(),
+            Some(Err(err)) => return Err(ParseError::LexError(err.clone())),
+            None => return Err(ParseError::Eof),
+        };
+
+        Ok(match kind {
+            Kind::Not | Kind::FormatSelection => Expr::Urnary(ExprUrnary::parse(tokens)?),
+            Kind::LocalCoordinate | Kind::RelativeCoordinate => {
+                Expr::Coord(ExprCoord::parse(tokens)?)
+            }
+            Kind::Selector => Expr::Target(ExprTarget::parse(tokens)?),
+            Kind::LeftBrace => Expr::Map(ExprMap::parse(tokens)?),
+            Kind::Limit => Expr::Range(ExprRange::parse(tokens)?),
+            Kind::Int(_) => {
+                let start = LitInt::parse(tokens)?;
+
+                match tokens.peek() {
+                    Some(Ok(Token {
+                        kind: Kind::Limit,
+                        span: _,
+                        file: _,
+                    })) => {
+                        let limit = extract_token!(tokens, Kind::Limit);
+                        let end = parse_opt_int(tokens)?;
+                        Expr::Range(ExprRange::new(Some(start), limit, end))
+                    }
+                    _ => Expr::Lit(Lit::Int(start)),
+                }
+            }
+            Kind::Float(_) | Kind::String(_) | Kind::Bool(_) | Kind::Path(_) => {
+                Expr::Lit(Lit::parse(tokens)?)
+            }
+            _ => {
+                let mut lookahead = Lookahead::new(tokens);
+                lookahead.peek::<ExprUrnary>();
+                lookahead.peek::<ExprCoord>();
+                lookahead.peek_labeled::<ExprTarget>("a selector");
+                lookahead.peek::<ExprMap>();
+                lookahead.peek::<ExprRange>();
+                lookahead.peek::<LitInt>();
+                lookahead.peek::<LitFloat>();
+                lookahead.peek::<LitString>();
+                lookahead.peek::<LitBool>();
+                lookahead.peek::<LitPath>();
+                return Err(lookahead.error());
+            }
+        })
     }
 }
 
 impl<'src> Parse<'src> for Expr<'src> {
     fn parse(tokens: &mut Peekable<TokenIter<'src>>) -> ParseResult<'src, Self> {
-        match tokens.peek() {}
+        Self::parse_bp(tokens, 0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExprBinary<'src> {
+    pub lhs: Box<Expr<'src>>,
+    pub op: ExprOperator,
+    pub rhs: Box<Expr<'src>>,
+}
+
+impl<'src> ExprBinary<'src> {
+    pub fn new(lhs: Box<Expr<'src>>, op: ExprOperator, rhs: Box<Expr<'src>>) -> Self {
+        Self { lhs, op, rhs }
+    }
+}
+
+impl<'src> Spanned for ExprBinary<'src> {
+    fn span(&self) -> Span {
+        Span::new(self.lhs.span().start, self.rhs.span().end)
     }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct ExprTarget<'src> {
     /// `@`
-    select: Token<'src>,
-    target: Ident<'src>,
-    params: Option<Table<'src, Ident<'src>>>,
+    pub select: Token<'src>,
+    pub target: Ident<'src>,
+    pub params: Option<Table<'src, Ident<'src>>>,
+}
+
+impl<'src> ExprTarget<'src> {
+    pub fn new(
+        select: Token<'src>,
+        target: Ident<'src>,
+        params: Option<Table<'src, Ident<'src>>>,
+    ) -> Self {
+        Self {
+            select,
+            target,
+            params,
+        }
+    }
 }
 
 impl<'src> Spanned for ExprTarget<'src> {
@@ -235,14 +461,72 @@ impl<'src> Spanned for ExprTarget<'src> {
     }
 }
 
+impl<'src> Parse<'src> for ExprTarget<'src> {
+    fn parse(tokens: &mut Peekable<TokenIter<'src>>) -> ParseResult<'src, Self> {
+        let select = extract_token!(tokens, Kind::Selector);
+        let target = Ident::parse(tokens)?;
+
+        let params = match tokens.peek() {
+            Some(Ok(Token {
+                kind: Kind::LeftBracket,
+                span: _,
+                file: _,
+            })) => Some(Table::<Ident<'src>>::parse(tokens)?),
+            Some(Ok(_)) => None,
+            Some(Err(e)) => return Err(ParseError::LexError(e.clone())),
+            None => None,
+        };
+
+        Ok(Self::new(select, target, params))
+    }
+}
+
+impl<'src> Peek<'src> for ExprTarget<'src> {
+    fn peek(tokens: &mut Peekable<TokenIter<'src>>) -> bool {
+        matches!(
+            tokens.peek(),
+            Some(Ok(Token {
+                kind: Kind::Selector,
+                span: _,
+                file: _,
+            }))
+        )
+    }
+
+    fn example() -> Kind<'src> {
+        Kind::Selector
+    }
+}
+
+#[test]
+fn expr_parse_error_describes_a_missing_selector_by_label() {
+    use crate::span::FileRef;
+    use logos::Logos;
+
+    let lex = Kind::lexer(",");
+    let mut tokens = TokenIter::new(lex, FileRef(0)).peekable();
+
+    let err = Expr::parse(&mut tokens).unwrap_err();
+    let ParseError::Unexpected { expected, .. } = err else {
+        panic!("expected ParseError::Unexpected, got {err:?}");
+    };
+    assert!(
+        expected.to_string().contains("a selector"),
+        "expected set should describe a missing ExprTarget as \"a selector\", got: {expected}"
+    );
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Table<'src, K> {
-    brackets: (Token<'src>, Token<'src>),
-    fields: Vec<TableField<'src, K>>,
+    pub brackets: (Token<'src>, Token<'src>),
+    pub fields: Separated<TableField<'src, K>, Comma<'src>, true>,
 }
 
 impl<'src, K> Table<'src, K> {
-    pub fn new(brackets: (Token<'src>, Token<'src>), fields: Vec<TableField<'src, K>>) -> Self {
+    pub fn new(
+        brackets: (Token<'src>, Token<'src>),
+        fields: Separated<TableField<'src, K>, Comma<'src>, true>,
+    ) -> Self {
         Self { brackets, fields }
     }
 }
@@ -260,22 +544,11 @@ where
     fn parse(tokens: &mut Peekable<TokenIter<'src>>) -> ParseResult<'src, Self> {
         let open = extract_token!(tokens, Kind::LeftBracket);
 
-        // A Vec does not allocate right away.
-        let mut fields = vec![];
+        let fields = Separated::<TableField<'src, K>, Comma<'src>, true>::parse_terminated(
+            tokens,
+            |kind| matches!(kind, Kind::RightBracket),
+        )?;
 
-        loop {
-            let field = TableField::<'src, K>::parse(tokens)?;
-            fields.push(field);
-            match tokens.peek() {
-                Some(Ok(Token {
-                    kind: Kind::RightBracket,
-                    span: _,
-                })) => break,
-                Some(Ok(_)) => continue,
-                Some(Err(e)) => return Err(ParseError::LexError(e.clone())),
-                None => return Err(ParseError::Eof),
-            }
-        }
         let close = extract_token!(tokens, Kind::RightBracket);
 
         Ok(Table::new((open, close), fields))
@@ -284,24 +557,17 @@ where
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct TableField<'src, K> {
-    key: K,
-    eq: Token<'src>,
-    value: Option<Expr<'src>>,
-    comma: Option<Token<'src>>,
+    pub key: K,
+    pub eq: Token<'src>,
+    pub value: Option<Expr<'src>>,
 }
 
 impl<'src, K> TableField<'src, K> {
-    pub fn new(
-        key: K,
-        assign: Token<'src>,
-        value: Option<Expr<'src>>,
-        comma: Option<Token<'src>>,
-    ) -> Self {
+    pub fn new(key: K, assign: Token<'src>, value: Option<Expr<'src>>) -> Self {
         Self {
             key,
             eq: assign,
             value,
-            comma,
         }
     }
 }
@@ -320,12 +586,289 @@ struct Field<K, Eq, V> {
     value: V,
 }
 
-struct Separated<T, Sep, const IS_TRAILING: bool> {
+/// A punctuated sequence of `T`, separated by `Sep` (the crate's equivalent
+/// of syn's `Punctuated`). `IS_TRAILING` controls whether a dangling `Sep`
+/// after the last value is permitted when parsing with `parse_terminated`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Separated<T, Sep, const IS_TRAILING: bool> {
     // This uses an SOA.
     values: Vec<T>,
     separators: Vec<Sep>,
 }
 
+impl<T, Sep, const IS_TRAILING: bool> Separated<T, Sep, IS_TRAILING> {
+    pub fn new() -> Self {
+        Self {
+            values: Vec::new(),
+            separators: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn first(&self) -> Option<&T> {
+        self.values.first()
+    }
+
+    pub fn last(&self) -> Option<&T> {
+        self.values.last()
+    }
+
+    pub fn push_value(&mut self, value: T) {
+        self.values.push(value);
+    }
+
+    pub fn push_punct(&mut self, sep: Sep) {
+        self.separators.push(sep);
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.values.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, T> {
+        self.values.iter_mut()
+    }
+
+    pub fn separators(&self) -> &[Sep] {
+        &self.separators
+    }
+
+    /// Transforms each value, leaving the separators untouched. Used by
+    /// [`crate::fold::Fold`] impls, which (like `Visit`/`VisitMut`) only
+    /// recurse into values, never punctuation.
+    pub fn map<U>(self, mut f: impl FnMut(T) -> U) -> Separated<U, Sep, IS_TRAILING> {
+        Separated {
+            values: self.values.into_iter().map(&mut f).collect(),
+            separators: self.separators,
+        }
+    }
+}
+
+impl<T, Sep, const IS_TRAILING: bool> Default for Separated<T, Sep, IS_TRAILING> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, Sep, const IS_TRAILING: bool> IntoIterator for Separated<T, Sep, IS_TRAILING> {
+    type Item = T;
+    type IntoIter = std::vec::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.values.into_iter()
+    }
+}
+
+impl<'a, T, Sep, const IS_TRAILING: bool> IntoIterator for &'a Separated<T, Sep, IS_TRAILING> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.values.iter()
+    }
+}
+
+impl<T, Sep, const IS_TRAILING: bool> Spanned for Separated<T, Sep, IS_TRAILING>
+where
+    T: Spanned,
+    Sep: Spanned,
+{
+    fn span(&self) -> Span {
+        let start = self
+            .values
+            .first()
+            .map(|v| v.span().start)
+            .unwrap_or_default();
+
+        let end = if IS_TRAILING
+            && let Some(sep) = self.separators.last()
+        {
+            sep.span().end
+        } else {
+            self.values.last().map(|v| v.span().end).unwrap_or(start)
+        };
+
+        Span::new(start, end)
+    }
+}
+
+impl<'src, T, Sep, const IS_TRAILING: bool> Separated<T, Sep, IS_TRAILING>
+where
+    T: Parse<'src>,
+{
+    /// Parses a run of values with no separator between them, stopping once
+    /// `stop` matches the next token's kind or the input is exhausted. Used
+    /// for whitespace-juxtaposed sequences (a command's argument list) where
+    /// `Sep` never actually occurs.
+    pub fn parse_until(
+        tokens: &mut Peekable<TokenIter<'src>>,
+        stop: impl Fn(&Kind<'src>) -> bool,
+    ) -> ParseResult<'src, Self> {
+        let mut this = Self::new();
+
+        loop {
+            match tokens.peek() {
+                Some(Ok(token)) if stop(&token.kind) => break,
+                Some(Err(err)) => return Err(ParseError::LexError(err.clone())),
+                None => break,
+                Some(Ok(_)) => {}
+            }
+
+            this.push_value(T::parse(tokens)?);
+        }
+
+        Ok(this)
+    }
+}
+
+impl<'src, T, Sep, const IS_TRAILING: bool> Separated<T, Sep, IS_TRAILING>
+where
+    T: Parse<'src>,
+    Sep: Parse<'src> + Peek<'src>,
+{
+    /// Parses values separated by `Sep`, stopping as soon as the next token
+    /// matches `stop` (typically a closing delimiter) or `Sep` is absent
+    /// after a value. A trailing `Sep` right before `stop` is only accepted
+    /// when `IS_TRAILING` is `true`.
+    pub fn parse_terminated(
+        tokens: &mut Peekable<TokenIter<'src>>,
+        stop: impl Fn(&Kind<'src>) -> bool,
+    ) -> ParseResult<'src, Self> {
+        let mut this = Self::new();
+
+        loop {
+            match tokens.peek() {
+                Some(Ok(token)) if stop(&token.kind) => break,
+                Some(Err(err)) => return Err(ParseError::LexError(err.clone())),
+                None => break,
+                Some(Ok(_)) => {}
+            }
+
+            this.push_value(T::parse(tokens)?);
+
+            if !Sep::peek(tokens) {
+                break;
+            }
+            this.push_punct(Sep::parse(tokens)?);
+
+            if !IS_TRAILING {
+                match tokens.peek() {
+                    Some(Ok(token)) if stop(&token.kind) => {
+                        return Err(ParseError::Unexpected {
+                            found: token.clone(),
+                            expected: ExpectedSet::new(),
+                        });
+                    }
+                    Some(Err(err)) => return Err(ParseError::LexError(err.clone())),
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(this)
+    }
+
+    /// Parses a non-empty sequence of values separated by `Sep`, stopping as
+    /// soon as `Sep` is absent after a value.
+    pub fn parse_separated_nonempty(
+        tokens: &mut Peekable<TokenIter<'src>>,
+    ) -> ParseResult<'src, Self> {
+        let mut this = Self::new();
+
+        loop {
+            this.push_value(T::parse(tokens)?);
+
+            if !Sep::peek(tokens) {
+                break;
+            }
+            this.push_punct(Sep::parse(tokens)?);
+        }
+
+        Ok(this)
+    }
+}
+
+/// The `,` separator token, as used by `Separated` to delimit table fields,
+/// map fields, and similar comma-delimited lists.
+#[derive(Debug, Clone, PartialEq, Parse)]
+pub struct Comma<'src>(#[token(Kind::Comma)] pub Token<'src>);
+
+impl<'src> Spanned for Comma<'src> {
+    fn span(&self) -> Span {
+        self.0.span()
+    }
+}
+
+impl<'src> Peek<'src> for Comma<'src> {
+    fn peek(tokens: &mut Peekable<TokenIter<'src>>) -> bool {
+        matches!(
+            tokens.peek(),
+            Some(Ok(Token {
+                kind: Kind::Comma,
+                span: _,
+                file: _,
+            }))
+        )
+    }
+
+    fn example() -> Kind<'src> {
+        Kind::Comma
+    }
+}
+
+/// Exercises `#[derive(Parse)]`'s `#[parse(delimited(..))]` and
+/// `#[parse(separated(..))]` attributes, which no real AST node uses (every
+/// bracketed/braced/comma-separated construct in `ast.rs` is hand-parsed
+/// instead), so without these the two code paths would never run.
+#[cfg(test)]
+#[derive(Debug, Clone, PartialEq, Parse)]
+struct BracedInt<'src> {
+    #[parse(delimited(LeftBrace, RightBrace))]
+    braced: Delimited<Token<'src>, LitInt, Token<'src>>,
+}
+
+#[cfg(test)]
+#[derive(Debug, Clone, PartialEq, Parse)]
+struct CommaSeparatedInts<'src> {
+    #[parse(separated(Comma))]
+    values: Separated<LitInt, Comma<'src>, false>,
+}
+
+#[test]
+fn derive_parse_delimited_attribute_parses_an_open_value_close_triple() {
+    use crate::span::FileRef;
+    use logos::Logos;
+
+    let src = "{42}";
+    let lex = Kind::lexer(src);
+    let mut tokens = TokenIter::new(lex, FileRef(0)).peekable();
+
+    let parsed = BracedInt::parse(&mut tokens).expect("should parse a braced int");
+    assert_eq!(parsed.braced.inner.value, 42);
+}
+
+#[test]
+fn derive_parse_separated_attribute_parses_a_comma_separated_list() {
+    use crate::span::FileRef;
+    use logos::Logos;
+
+    let src = "1,2,3";
+    let lex = Kind::lexer(src);
+    let mut tokens = TokenIter::new(lex, FileRef(0)).peekable();
+
+    let parsed =
+        CommaSeparatedInts::parse(&mut tokens).expect("should parse a comma-separated list");
+    let values: Vec<i32> = parsed.values.into_iter().map(|v| v.value).collect();
+    assert_eq!(values, vec![1, 2, 3]);
+}
+
 impl<K, S, V> Spanned for Field<K, S, V>
 where
     K: Spanned,
@@ -344,19 +887,26 @@ where
         let ident = K::parse(tokens)?;
 
         let assign = extract_token!(tokens, Kind::Equal);
-        let mut comma = None;
+
+        // A field's value is terminated by the table's `,` separator or its
+        // closing `]`, both of which `Separated` is responsible for; this
+        // only peeks for them to know whether a value is present at all.
+        let at_end = |tokens: &mut Peekable<TokenIter<'src>>| {
+            matches!(
+                tokens.peek(),
+                Some(Ok(Token {
+                    kind: Kind::Comma | Kind::RightBracket,
+                    span: _,
+                    file: _,
+                })) | None
+            )
+        };
 
         let value = match tokens.peek() {
-            Some(Ok(Token {
-                kind: Kind::Comma,
-                span: _,
-            })) => {
-                comma = extract_token!(tokens, Option<Kind::Comma>);
-                None
-            }
             Some(Ok(Token {
                 kind: Kind::Not,
                 span: _,
+                file: _,
             })) => {
                 // TODO: This could just take the span from the `_` and
                 // simply clone, rather than extracting the token,
@@ -369,12 +919,7 @@ where
                     unreachable!()
                 };
 
-                let expr = if let Some(Ok(Token {
-                    kind: Kind::Comma,
-                    span: _,
-                })) = tokens.peek()
-                {
-                    comma = extract_token!(tokens, Option<Kind::Comma>);
+                let expr = if at_end(tokens) {
                     None
                 } else {
                     Some(Box::new(Expr::parse(tokens)?))
@@ -385,23 +930,24 @@ where
                 Some(Expr::Urnary(urnary))
             }
             Some(Ok(_)) => {
-                let expr = Expr::parse(tokens)?;
-
-                comma = extract_token!(tokens, Option<Kind::Comma>);
-                Some(expr)
+                if at_end(tokens) {
+                    None
+                } else {
+                    Some(Expr::parse(tokens)?)
+                }
             }
             Some(Err(err)) => return Err(ParseError::LexError(err.clone())),
             None => return Err(ParseError::Eof),
         };
 
-        Ok(TableField::new(ident, assign, value, comma))
+        Ok(TableField::new(ident, assign, value))
     }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Ident<'src> {
-    value: Cow<'src, str>,
-    span: Span,
+    pub value: Cow<'src, str>,
+    pub span: Span,
 }
 
 impl<'src> Ident<'src> {
@@ -425,23 +971,44 @@ impl<'src> Parse<'src> for Ident<'src> {
         match token {
             Ok(Token {
                 span,
-                kind: Kind::Ident(s),
-            }) => Ok(Ident::new(Cow::Borrowed(s), span)),
-
-            Ok(tok) => Err(ParseError::InvalidToken(tok)),
+                kind: Kind::Ident(interned),
+                file: _,
+            }) => Ok(Ident::new(Cow::Borrowed(interned.text), span)),
+
+            Ok(tok) => Err(ParseError::Unexpected {
+                found: tok,
+                expected: ExpectedSet::of_kind(Kind::Ident(Interned::default())),
+            }),
             Err(e) => Err(ParseError::LexError(e)),
         }
     }
 }
 
+impl<'src> Peek<'src> for Ident<'src> {
+    fn peek(tokens: &mut Peekable<TokenIter<'src>>) -> bool {
+        matches!(
+            tokens.peek(),
+            Some(Ok(Token {
+                kind: Kind::Ident(_),
+                span: _,
+                file: _,
+            }))
+        )
+    }
+
+    fn example() -> Kind<'src> {
+        Kind::Ident(Interned::default())
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct ExprRange<'src> {
-    start: Option<LitInt>,
+    pub start: Option<LitInt>,
 
     /// `..`
-    limit: Token<'src>,
+    pub limit: Token<'src>,
 
-    end: Option<LitInt>,
+    pub end: Option<LitInt>,
 }
 
 impl<'src> ExprRange<'src> {
@@ -467,22 +1034,26 @@ impl<'src> Spanned for ExprRange<'src> {
     }
 }
 
+/// Parses an optional `LitInt`, leaving the token stream untouched if the
+/// next token isn't one. Shared by `ExprRange` (for the integers before and
+/// after its `..`) and the `Expr` atom parser (for the integer after a `..`).
+fn parse_opt_int<'src>(
+    tokens: &mut Peekable<TokenIter<'src>>,
+) -> ParseResult<'src, Option<LitInt>> {
+    Ok(match tokens.peek() {
+        Some(Ok(Token {
+            kind: Kind::Int(_),
+            span: _,
+            file: _,
+        })) => Some(LitInt::parse(tokens)?),
+        Some(Ok(_)) => None,
+        Some(Err(err)) => return Err(ParseError::LexError(err.clone())),
+        None => None,
+    })
+}
+
 impl<'src> Parse<'src> for ExprRange<'src> {
     fn parse(tokens: &mut Peekable<TokenIter<'src>>) -> ParseResult<'src, Self> {
-        fn parse_opt_int<'src>(
-            tokens: &mut Peekable<TokenIter<'src>>,
-        ) -> ParseResult<'src, Option<LitInt>> {
-            Ok(match tokens.peek() {
-                Some(Ok(Token {
-                    kind: Kind::Int(_),
-                    span: _,
-                })) => Some(LitInt::parse(tokens)?),
-                Some(Ok(_)) => None,
-                Some(Err(err)) => return Err(ParseError::LexError(err.clone())),
-                None => return Err(ParseError::Eof),
-            })
-        }
-
         let start = parse_opt_int(tokens)?;
         let limit = extract_token!(tokens, Kind::Limit);
         let end = parse_opt_int(tokens)?;
@@ -491,6 +1062,23 @@ impl<'src> Parse<'src> for ExprRange<'src> {
     }
 }
 
+impl<'src> Peek<'src> for ExprRange<'src> {
+    fn peek(tokens: &mut Peekable<TokenIter<'src>>) -> bool {
+        matches!(
+            tokens.peek(),
+            Some(Ok(Token {
+                kind: Kind::Limit | Kind::Int(_),
+                span: _,
+                file: _,
+            }))
+        )
+    }
+
+    fn example() -> Kind<'src> {
+        Kind::Limit
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct ExprUrnary<'src> {
     pub op: UnOp<'src>,
@@ -526,12 +1114,6 @@ pub enum UnOp<'src> {
     ///// `-`
     //Neg(Token<'src>),
     //
-    /// `~`
-    LocalCoordinate(Token<'src>),
-
-    /// `^`
-    RelativeCoordinate(Token<'src>),
-
     /// `ยง`
     FormatSelection(Token<'src>),
 }
@@ -541,8 +1123,6 @@ impl<'src> Spanned for UnOp<'src> {
         match self {
             UnOp::Not(token) => token.span(),
             // UnOp::Neg(token) => token.span(),
-            UnOp::LocalCoordinate(token) => token.span(),
-            UnOp::RelativeCoordinate(token) => token.span(),
             UnOp::FormatSelection(token) => token.span(),
         }
     }
@@ -560,45 +1140,251 @@ impl<'src> Parse<'src> for ExprUrnary<'src> {
                     t @ Token {
                         span: _,
                         kind: Kind::Not,
+                        file: _,
                     },
                 ) => UnOp::Not(t),
                 // Ok(
                 //     t @ Token {
                 //         span: _,
                 //         kind: Kind::Neg,
+                //         file: _,
                 //     },
                 // ) => UnOp::Neg(t),
-                Ok(
-                    t @ Token {
-                        span: _,
-                        kind: Kind::LocalCoordinate,
-                    },
-                ) => UnOp::LocalCoordinate(t),
-                Ok(
-                    t @ Token {
-                        span: _,
-                        kind: Kind::RelativeCoordinate,
-                    },
-                ) => UnOp::RelativeCoordinate(t),
                 Ok(
                     t @ Token {
                         span: _,
                         kind: Kind::FormatSelection,
+                        file: _,
                     },
                 ) => UnOp::FormatSelection(t),
-                Ok(tok) => return Err(ParseError::InvalidToken(tok)),
+                Ok(tok) => {
+                    return Err(ParseError::Unexpected {
+                        found: tok,
+                        expected: ExpectedSet::of_kinds([Kind::Not, Kind::FormatSelection]),
+                    });
+                }
                 Err(e) => return Err(ParseError::LexError(e)),
             }
         };
 
-        // TODO: Support ~~~
         let expr = Expr::parse(tokens)?;
 
         Ok(Self::new(op, Some(Box::new(expr))))
     }
 }
 
+impl<'src> Peek<'src> for ExprUrnary<'src> {
+    fn peek(tokens: &mut Peekable<TokenIter<'src>>) -> bool {
+        matches!(
+            tokens.peek(),
+            Some(Ok(Token {
+                kind: Kind::Not | Kind::FormatSelection,
+                span: _,
+                file: _,
+            }))
+        )
+    }
+
+    fn example() -> Kind<'src> {
+        Kind::Not
+    }
+}
+
+/// Which marker (if any) preceded a coordinate component's offset.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CoordKind {
+    /// No marker: an absolute coordinate.
+    Absolute,
+    /// `~`: relative to the command's local execution position.
+    Local,
+    /// `^`: relative to the executor's facing direction.
+    Relative,
+}
+
+/// One component of an [`ExprCoord`] triple: a marker (`~`/`^`/none) and an
+/// optional numeric offset. A bare `~` or `^` with no offset means "the
+/// current value"; an absolute component always carries its value as the
+/// offset, since it has no marker of its own.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExprCoordComponent<'src> {
+    pub kind: CoordKind,
+    pub marker: Option<Token<'src>>,
+    pub offset: Option<Lit<'src>>,
+}
+
+impl<'src> ExprCoordComponent<'src> {
+    pub fn new(kind: CoordKind, marker: Option<Token<'src>>, offset: Option<Lit<'src>>) -> Self {
+        Self {
+            kind,
+            marker,
+            offset,
+        }
+    }
+}
+
+impl<'src> Spanned for ExprCoordComponent<'src> {
+    fn span(&self) -> Span {
+        match (&self.marker, &self.offset) {
+            (Some(marker), Some(offset)) => Span::new(marker.span.start, offset.span().end),
+            (Some(marker), None) => marker.span(),
+            (None, Some(offset)) => offset.span(),
+            (None, None) => Span::new(0, 0),
+        }
+    }
+}
+
+impl<'src> ExprCoordComponent<'src> {
+    fn parse(tokens: &mut Peekable<TokenIter<'src>>) -> ParseResult<'src, Self> {
+        let peek_numeric = |tokens: &mut Peekable<TokenIter<'src>>| {
+            matches!(
+                tokens.peek(),
+                Some(Ok(Token {
+                    kind: Kind::Int(_) | Kind::Float(_),
+                    span: _,
+                    file: _,
+                }))
+            )
+        };
+
+        match tokens.peek() {
+            Some(Ok(Token {
+                kind: Kind::RelativeCoordinate,
+                span: _,
+                file: _,
+            })) => {
+                let marker = extract_token!(tokens, Kind::RelativeCoordinate);
+                let offset = if peek_numeric(tokens) {
+                    Some(Lit::parse(tokens)?)
+                } else {
+                    None
+                };
+                Ok(Self::new(CoordKind::Local, Some(marker), offset))
+            }
+            Some(Ok(Token {
+                kind: Kind::LocalCoordinate,
+                span: _,
+                file: _,
+            })) => {
+                let marker = extract_token!(tokens, Kind::LocalCoordinate);
+                let offset = if peek_numeric(tokens) {
+                    Some(Lit::parse(tokens)?)
+                } else {
+                    None
+                };
+                Ok(Self::new(CoordKind::Relative, Some(marker), offset))
+            }
+            Some(Ok(Token {
+                kind: Kind::Int(_) | Kind::Float(_),
+                span: _,
+                file: _,
+            })) => {
+                let offset = Lit::parse(tokens)?;
+                Ok(Self::new(CoordKind::Absolute, None, Some(offset)))
+            }
+            Some(Ok(tok)) => Err(ParseError::Unexpected {
+                found: tok.clone(),
+                expected: ExpectedSet::of_label("a coordinate"),
+            }),
+            Some(Err(e)) => Err(ParseError::LexError(e.clone())),
+            None => Err(ParseError::Eof),
+        }
+    }
+}
+
+/// A Minecraft-style position argument: three whitespace-juxtaposed
+/// coordinate components (e.g. `~ ~1 ~-2` or `^ ^ ^3`), each independently
+/// absolute, local (`~`), or relative (`^`).
 #[derive(Debug, Clone, PartialEq)]
+pub struct ExprCoord<'src> {
+    pub components: [ExprCoordComponent<'src>; 3],
+}
+
+impl<'src> ExprCoord<'src> {
+    pub fn new(components: [ExprCoordComponent<'src>; 3]) -> Self {
+        Self { components }
+    }
+}
+
+impl<'src> Spanned for ExprCoord<'src> {
+    fn span(&self) -> Span {
+        Span::new(
+            self.components[0].span().start,
+            self.components[2].span().end,
+        )
+    }
+}
+
+impl<'src> Parse<'src> for ExprCoord<'src> {
+    fn parse(tokens: &mut Peekable<TokenIter<'src>>) -> ParseResult<'src, Self> {
+        let first = ExprCoordComponent::parse(tokens)?;
+        let second = ExprCoordComponent::parse(tokens)?;
+        let third = ExprCoordComponent::parse(tokens)?;
+
+        Ok(Self::new([first, second, third]))
+    }
+}
+
+impl<'src> Peek<'src> for ExprCoord<'src> {
+    fn peek(tokens: &mut Peekable<TokenIter<'src>>) -> bool {
+        matches!(
+            tokens.peek(),
+            Some(Ok(Token {
+                kind: Kind::LocalCoordinate | Kind::RelativeCoordinate,
+                span: _,
+                file: _,
+            }))
+        )
+    }
+
+    fn example() -> Kind<'src> {
+        Kind::RelativeCoordinate
+    }
+}
+
+/// `Kind::RelativeCoordinate` (`~`) maps to `CoordKind::Local` and
+/// `Kind::LocalCoordinate` (`^`) maps to `CoordKind::Relative` — the two are
+/// named confusingly in baseline, and easy to swap by accident.
+#[test]
+fn coordinate_markers_map_to_the_right_coord_kind() {
+    use crate::span::FileRef;
+    use logos::Logos;
+
+    fn parse_coord(src: &str) -> ExprCoord<'_> {
+        let lex = Kind::lexer(src);
+        let mut tokens = TokenIter::new(lex, FileRef(0)).peekable();
+        ExprCoord::parse(&mut tokens).expect("should parse as a coordinate")
+    }
+
+    fn int_offset(component: &ExprCoordComponent<'_>) -> Option<i32> {
+        match &component.offset {
+            Some(Lit::Int(i)) => Some(i.value),
+            None => None,
+            other => panic!("expected an int offset or none, got {other:?}"),
+        }
+    }
+
+    let local = parse_coord("~ ~1 ~-2");
+    assert!(local
+        .components
+        .iter()
+        .all(|c| c.kind == CoordKind::Local));
+    assert_eq!(
+        local.components.each_ref().map(int_offset),
+        [None, Some(1), Some(-2)]
+    );
+
+    let relative = parse_coord("^ ^ ^3");
+    assert!(relative
+        .components
+        .iter()
+        .all(|c| c.kind == CoordKind::Relative));
+    assert_eq!(
+        relative.components.each_ref().map(int_offset),
+        [None, None, Some(3)]
+    );
+}
+
+#[derive(Debug, Clone, PartialEq, Parse)]
 pub enum Lit<'src> {
     Int(LitInt),
     String(LitString<'src>),
@@ -619,30 +1405,6 @@ impl<'src> Spanned for Lit<'src> {
     }
 }
 
-impl<'src> Parse<'src> for Lit<'src> {
-    fn parse(tokens: &mut Peekable<TokenIter<'src>>) -> ParseResult<'src, Self> {
-        let token = match tokens.peek() {
-            Some(s) => s,
-            None => return Err(ParseError::Eof),
-        };
-
-        let token @ Token { kind, span: _ } = match token {
-            Ok(ok) => ok,
-            Err(e) => return Err(ParseError::LexError(e.clone())),
-        };
-
-        Ok(match kind {
-            Kind::Float(_) => Lit::Float(LitFloat::parse(tokens)?),
-            Kind::Int(_) => Lit::Int(LitInt::parse(tokens)?),
-            Kind::String(_) => Lit::String(LitString::parse(tokens)?),
-            Kind::Path(_) => Lit::Path(LitPath::parse(tokens)?),
-            Kind::Bool(_) => Lit::Bool(LitBool::parse(tokens)?),
-
-            _ => return Err(ParseError::InvalidToken(token.clone())),
-        })
-    }
-}
-
 #[derive(Debug, Clone, PartialEq)]
 pub struct LitInt {
     pub value: i32,
@@ -672,13 +1434,34 @@ impl<'src> Parse<'src> for LitInt {
             Ok(Token {
                 span,
                 kind: Kind::Int(s),
+                file: _,
             }) => Ok(Self::new(s, span)),
-            Ok(tok) => Err(ParseError::InvalidToken(tok)),
+            Ok(tok) => Err(ParseError::Unexpected {
+                found: tok,
+                expected: ExpectedSet::of_kind(Kind::Int(0)),
+            }),
             Err(e) => Err(ParseError::LexError(e)),
         }
     }
 }
 
+impl<'src> Peek<'src> for LitInt {
+    fn peek(tokens: &mut Peekable<TokenIter<'src>>) -> bool {
+        matches!(
+            tokens.peek(),
+            Some(Ok(Token {
+                kind: Kind::Int(_),
+                span: _,
+                file: _,
+            }))
+        )
+    }
+
+    fn example() -> Kind<'src> {
+        Kind::Int(0)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct LitFloat {
     pub value: f32,
@@ -708,13 +1491,34 @@ impl<'src> Parse<'src> for LitFloat {
             Ok(Token {
                 span,
                 kind: Kind::Float(s),
+                file: _,
             }) => Ok(Self::new(s, span)),
-            Ok(tok) => Err(ParseError::InvalidToken(tok)),
+            Ok(tok) => Err(ParseError::Unexpected {
+                found: tok,
+                expected: ExpectedSet::of_kind(Kind::Float(0.0)),
+            }),
             Err(e) => Err(ParseError::LexError(e)),
         }
     }
 }
 
+impl<'src> Peek<'src> for LitFloat {
+    fn peek(tokens: &mut Peekable<TokenIter<'src>>) -> bool {
+        matches!(
+            tokens.peek(),
+            Some(Ok(Token {
+                kind: Kind::Float(_),
+                span: _,
+                file: _,
+            }))
+        )
+    }
+
+    fn example() -> Kind<'src> {
+        Kind::Float(0.0)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct LitString<'src> {
     pub value: Cow<'src, str>,
@@ -744,17 +1548,47 @@ impl<'src> Parse<'src> for LitString<'src> {
             Ok(Token {
                 span,
                 kind: Kind::String(s),
-            }) => Ok(Self::new(Cow::Borrowed(s), span)),
-            Ok(tok) => Err(ParseError::InvalidToken(tok)),
+                file: _,
+            }) => Ok(Self::new(s, span)),
+            Ok(tok) => Err(ParseError::Unexpected {
+                found: tok,
+                expected: ExpectedSet::of_kind(Kind::String(Cow::Borrowed(""))),
+            }),
             Err(e) => Err(ParseError::LexError(e)),
         }
     }
 }
 
+impl<'src> Peek<'src> for LitString<'src> {
+    fn peek(tokens: &mut Peekable<TokenIter<'src>>) -> bool {
+        matches!(
+            tokens.peek(),
+            Some(Ok(Token {
+                kind: Kind::String(_),
+                span: _,
+                file: _,
+            }))
+        )
+    }
+
+    fn example() -> Kind<'src> {
+        Kind::String(Cow::Borrowed(""))
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct ExprMap<'src> {
     pub curlies: (Token<'src>, Token<'src>),
-    pub fields: Vec<ExprMapField<'src>>,
+    pub fields: Separated<ExprMapField<'src>, Comma<'src>, true>,
+}
+
+impl<'src> ExprMap<'src> {
+    pub fn new(
+        curlies: (Token<'src>, Token<'src>),
+        fields: Separated<ExprMapField<'src>, Comma<'src>, true>,
+    ) -> Self {
+        Self { curlies, fields }
+    }
 }
 
 impl<'src> Spanned for ExprMap<'src> {
@@ -763,41 +1597,54 @@ impl<'src> Spanned for ExprMap<'src> {
     }
 }
 
+impl<'src> Parse<'src> for ExprMap<'src> {
+    fn parse(tokens: &mut Peekable<TokenIter<'src>>) -> ParseResult<'src, Self> {
+        let open = extract_token!(tokens, Kind::LeftBrace);
+
+        let fields = Separated::<ExprMapField<'src>, Comma<'src>, true>::parse_terminated(
+            tokens,
+            |kind| matches!(kind, Kind::RightBrace),
+        )?;
+
+        let close = extract_token!(tokens, Kind::RightBrace);
+
+        Ok(Self::new((open, close), fields))
+    }
+}
+
+impl<'src> Peek<'src> for ExprMap<'src> {
+    fn peek(tokens: &mut Peekable<TokenIter<'src>>) -> bool {
+        matches!(
+            tokens.peek(),
+            Some(Ok(Token {
+                kind: Kind::LeftBrace,
+                span: _,
+                file: _,
+            }))
+        )
+    }
+
+    fn example() -> Kind<'src> {
+        Kind::LeftBrace
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct ExprMapField<'src> {
     pub key: LitString<'src>,
     pub colon: Token<'src>,
     pub value: Expr<'src>,
-
-    pub comma: Option<Token<'src>>,
 }
 
 impl<'src> ExprMapField<'src> {
-    pub fn new(
-        key: LitString<'src>,
-        colon: Token<'src>,
-        value: Expr<'src>,
-        comma: Option<Token<'src>>,
-    ) -> Self {
-        Self {
-            key,
-            colon,
-            value,
-            comma,
-        }
+    pub fn new(key: LitString<'src>, colon: Token<'src>, value: Expr<'src>) -> Self {
+        Self { key, colon, value }
     }
 }
 
 impl<'src> Spanned for ExprMapField<'src> {
     fn span(&self) -> Span {
-        Span::new(
-            self.key.span.start,
-            if let Some(s) = self.comma.as_ref() {
-                s.span.end
-            } else {
-                self.value.span().end
-            },
-        )
+        Span::new(self.key.span.start, self.value.span().end)
     }
 }
 
@@ -807,9 +1654,7 @@ impl<'src> Parse<'src> for ExprMapField<'src> {
         let colon = extract_token!(tokens, Kind::Colon);
         let value = Expr::parse(tokens)?;
 
-        let comma = extract_token!(tokens, Option<Kind::Comma>);
-
-        Ok(Self::new(key, colon, value, comma))
+        Ok(Self::new(key, colon, value))
     }
 }
 
@@ -842,19 +1687,46 @@ impl<'src> Parse<'src> for LitBool {
             Ok(Token {
                 span,
                 kind: Kind::Bool(s),
+                file: _,
             }) => Ok(Self::new(s, span)),
-            Ok(tok) => Err(ParseError::InvalidToken(tok)),
+            Ok(tok) => Err(ParseError::Unexpected {
+                found: tok,
+                expected: ExpectedSet::of_kind(Kind::Bool(false)),
+            }),
             Err(e) => Err(ParseError::LexError(e)),
         }
     }
 }
 
+impl<'src> Peek<'src> for LitBool {
+    fn peek(tokens: &mut Peekable<TokenIter<'src>>) -> bool {
+        matches!(
+            tokens.peek(),
+            Some(Ok(Token {
+                kind: Kind::Bool(_),
+                span: _,
+                file: _,
+            }))
+        )
+    }
+
+    fn example() -> Kind<'src> {
+        Kind::Bool(false)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct ExprOperator {
     pub value: Operator,
     pub span: Span,
 }
 
+impl ExprOperator {
+    pub fn new(value: Operator, span: Span) -> Self {
+        Self { value, span }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Operator {
     /// `<>` or `=`
@@ -868,6 +1740,38 @@ pub enum Operator {
     Wildcard,
 }
 
+/// Maps a token kind to the `Operator` it represents, or `None` if it isn't
+/// a binary operator at all.
+fn operator_for(kind: &Kind<'_>) -> Option<Operator> {
+    Some(match kind {
+        Kind::Equal => Operator::Equal,
+        Kind::AddAssign => Operator::AddAssign,
+        Kind::SubAssign => Operator::SubAssign,
+        Kind::MulAssign => Operator::MulAssign,
+        Kind::DivAssign => Operator::DivAssign,
+        Kind::Gt => Operator::Gt,
+        Kind::Lt => Operator::Lt,
+        Kind::Wildcard => Operator::Wildcard,
+        _ => return None,
+    })
+}
+
+/// Binding powers `(left, right)` used by `Expr::parse_bp`'s precedence
+/// climbing. Comparisons bind tighter than the scoreboard assignment family;
+/// the assignment family is right-associative (`right == left`), comparisons
+/// are left-associative (`right == left + 1`).
+fn infix_bp(op: &Operator) -> (u8, u8) {
+    match op {
+        Operator::Gt | Operator::Lt => (10, 11),
+        Operator::Equal
+        | Operator::AddAssign
+        | Operator::SubAssign
+        | Operator::MulAssign
+        | Operator::DivAssign
+        | Operator::Wildcard => (5, 5),
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct StmtComment<'src> {
     pub value: Cow<'src, str>,
@@ -897,8 +1801,12 @@ impl<'src> Parse<'src> for StmtComment<'src> {
             Ok(Token {
                 span,
                 kind: Kind::Comment(s),
+                file: _,
             }) => Ok(Self::new(Cow::Borrowed(s), span)),
-            Ok(tok) => Err(ParseError::InvalidToken(tok)),
+            Ok(tok) => Err(ParseError::Unexpected {
+                found: tok,
+                expected: ExpectedSet::of_kind(Kind::Comment("")),
+            }),
             Err(e) => Err(ParseError::LexError(e)),
         }
     }
@@ -932,10 +1840,31 @@ impl<'src> Parse<'src> for LitPath<'src> {
         match token {
             Ok(Token {
                 span,
-                kind: Kind::Path(s),
-            }) => Ok(Self::new(Cow::Borrowed(s), span)),
-            Ok(tok) => Err(ParseError::InvalidToken(tok)),
+                kind: Kind::Path(interned),
+                file: _,
+            }) => Ok(Self::new(Cow::Borrowed(interned.text), span)),
+            Ok(tok) => Err(ParseError::Unexpected {
+                found: tok,
+                expected: ExpectedSet::of_kind(Kind::Path(Interned::default())),
+            }),
             Err(e) => Err(ParseError::LexError(e)),
         }
     }
 }
+
+impl<'src> Peek<'src> for LitPath<'src> {
+    fn peek(tokens: &mut Peekable<TokenIter<'src>>) -> bool {
+        matches!(
+            tokens.peek(),
+            Some(Ok(Token {
+                kind: Kind::Path(_),
+                span: _,
+                file: _,
+            }))
+        )
+    }
+
+    fn example() -> Kind<'src> {
+        Kind::Path(Interned::default())
+    }
+}
@@ -0,0 +1,326 @@
+//! Owned tree rewrite, the by-value counterpart to [`crate::visit`] and
+//! [`crate::visit_mut`]: one method per node type, consuming the node and
+//! returning a (possibly different) replacement. Each default implementation
+//! recurses into the node's children via a `fold_*` free function and
+//! reassembles the node from the folded children. Override a method to
+//! rewrite that node type, e.g. replacing every `LitPath`.
+
+use crate::ast::{
+    CoordKind, Expr, ExprBinary, ExprCoord, ExprCoordComponent, ExprMap, ExprMapField,
+    ExprOperator, ExprRange, ExprTarget, ExprUrnary, Function, Ident, Lit, LitBool, LitFloat,
+    LitInt, LitPath, LitString, Stmt, StmtComment, StmtCommand, Table, TableField, UnOp,
+};
+use crate::span::Span;
+
+pub trait Fold<'src> {
+    fn fold_function(&mut self, node: Function<'src>) -> Function<'src> {
+        fold_function(self, node)
+    }
+
+    fn fold_stmt(&mut self, node: Stmt<'src>) -> Stmt<'src> {
+        fold_stmt(self, node)
+    }
+
+    fn fold_stmt_command(&mut self, node: StmtCommand<'src>) -> StmtCommand<'src> {
+        fold_stmt_command(self, node)
+    }
+
+    fn fold_stmt_comment(&mut self, node: StmtComment<'src>) -> StmtComment<'src> {
+        node
+    }
+
+    fn fold_stmt_error(&mut self, node: Span) -> Span {
+        node
+    }
+
+    fn fold_expr(&mut self, node: Expr<'src>) -> Expr<'src> {
+        fold_expr(self, node)
+    }
+
+    fn fold_expr_binary(&mut self, node: ExprBinary<'src>) -> ExprBinary<'src> {
+        fold_expr_binary(self, node)
+    }
+
+    fn fold_expr_target(&mut self, node: ExprTarget<'src>) -> ExprTarget<'src> {
+        fold_expr_target(self, node)
+    }
+
+    fn fold_expr_range(&mut self, node: ExprRange<'src>) -> ExprRange<'src> {
+        fold_expr_range(self, node)
+    }
+
+    fn fold_expr_urnary(&mut self, node: ExprUrnary<'src>) -> ExprUrnary<'src> {
+        fold_expr_urnary(self, node)
+    }
+
+    fn fold_expr_coord(&mut self, node: ExprCoord<'src>) -> ExprCoord<'src> {
+        fold_expr_coord(self, node)
+    }
+
+    fn fold_expr_coord_component(
+        &mut self,
+        node: ExprCoordComponent<'src>,
+    ) -> ExprCoordComponent<'src> {
+        fold_expr_coord_component(self, node)
+    }
+
+    fn fold_coord_kind(&mut self, node: CoordKind) -> CoordKind {
+        node
+    }
+
+    fn fold_expr_map(&mut self, node: ExprMap<'src>) -> ExprMap<'src> {
+        fold_expr_map(self, node)
+    }
+
+    fn fold_expr_map_field(&mut self, node: ExprMapField<'src>) -> ExprMapField<'src> {
+        fold_expr_map_field(self, node)
+    }
+
+    fn fold_expr_operator(&mut self, node: ExprOperator) -> ExprOperator {
+        node
+    }
+
+    fn fold_un_op(&mut self, node: UnOp<'src>) -> UnOp<'src> {
+        node
+    }
+
+    fn fold_table(&mut self, node: Table<'src, Ident<'src>>) -> Table<'src, Ident<'src>> {
+        fold_table(self, node)
+    }
+
+    fn fold_table_field(
+        &mut self,
+        node: TableField<'src, Ident<'src>>,
+    ) -> TableField<'src, Ident<'src>> {
+        fold_table_field(self, node)
+    }
+
+    fn fold_lit(&mut self, node: Lit<'src>) -> Lit<'src> {
+        fold_lit(self, node)
+    }
+
+    fn fold_lit_int(&mut self, node: LitInt) -> LitInt {
+        node
+    }
+
+    fn fold_lit_float(&mut self, node: LitFloat) -> LitFloat {
+        node
+    }
+
+    fn fold_lit_string(&mut self, node: LitString<'src>) -> LitString<'src> {
+        node
+    }
+
+    fn fold_lit_bool(&mut self, node: LitBool) -> LitBool {
+        node
+    }
+
+    fn fold_lit_path(&mut self, node: LitPath<'src>) -> LitPath<'src> {
+        node
+    }
+
+    fn fold_ident(&mut self, node: Ident<'src>) -> Ident<'src> {
+        node
+    }
+}
+
+pub fn fold_function<'src, F>(f: &mut F, node: Function<'src>) -> Function<'src>
+where
+    F: Fold<'src> + ?Sized,
+{
+    Function::new(
+        node.statements
+            .into_iter()
+            .map(|stmt| f.fold_stmt(stmt))
+            .collect(),
+    )
+}
+
+pub fn fold_stmt<'src, F>(f: &mut F, node: Stmt<'src>) -> Stmt<'src>
+where
+    F: Fold<'src> + ?Sized,
+{
+    match node {
+        Stmt::Command(c) => Stmt::Command(f.fold_stmt_command(c)),
+        Stmt::Comment(c) => Stmt::Comment(f.fold_stmt_comment(c)),
+        Stmt::Error(span) => Stmt::Error(f.fold_stmt_error(span)),
+    }
+}
+
+pub fn fold_stmt_command<'src, F>(f: &mut F, node: StmtCommand<'src>) -> StmtCommand<'src>
+where
+    F: Fold<'src> + ?Sized,
+{
+    StmtCommand::new(
+        node.slash,
+        f.fold_ident(node.ident),
+        node.arguments
+            .map(|arguments| arguments.map(|arg| f.fold_expr(arg))),
+    )
+}
+
+pub fn fold_expr<'src, F>(f: &mut F, node: Expr<'src>) -> Expr<'src>
+where
+    F: Fold<'src> + ?Sized,
+{
+    match node {
+        Expr::Lit(lit) => Expr::Lit(f.fold_lit(lit)),
+        Expr::Urnary(u) => Expr::Urnary(f.fold_expr_urnary(u)),
+        Expr::Range(r) => Expr::Range(f.fold_expr_range(r)),
+        Expr::Map(m) => Expr::Map(f.fold_expr_map(m)),
+        Expr::Target(t) => Expr::Target(f.fold_expr_target(t)),
+        Expr::Binary(b) => Expr::Binary(f.fold_expr_binary(b)),
+        Expr::Coord(c) => Expr::Coord(f.fold_expr_coord(c)),
+    }
+}
+
+pub fn fold_expr_binary<'src, F>(f: &mut F, node: ExprBinary<'src>) -> ExprBinary<'src>
+where
+    F: Fold<'src> + ?Sized,
+{
+    ExprBinary::new(
+        Box::new(f.fold_expr(*node.lhs)),
+        f.fold_expr_operator(node.op),
+        Box::new(f.fold_expr(*node.rhs)),
+    )
+}
+
+pub fn fold_expr_target<'src, F>(f: &mut F, node: ExprTarget<'src>) -> ExprTarget<'src>
+where
+    F: Fold<'src> + ?Sized,
+{
+    ExprTarget::new(
+        node.select,
+        f.fold_ident(node.target),
+        node.params.map(|params| f.fold_table(params)),
+    )
+}
+
+pub fn fold_expr_range<'src, F>(f: &mut F, node: ExprRange<'src>) -> ExprRange<'src>
+where
+    F: Fold<'src> + ?Sized,
+{
+    ExprRange::new(
+        node.start.map(|start| f.fold_lit_int(start)),
+        node.limit,
+        node.end.map(|end| f.fold_lit_int(end)),
+    )
+}
+
+pub fn fold_expr_urnary<'src, F>(f: &mut F, node: ExprUrnary<'src>) -> ExprUrnary<'src>
+where
+    F: Fold<'src> + ?Sized,
+{
+    ExprUrnary::new(
+        f.fold_un_op(node.op),
+        node.expr.map(|expr| Box::new(f.fold_expr(*expr))),
+    )
+}
+
+pub fn fold_expr_coord<'src, F>(f: &mut F, node: ExprCoord<'src>) -> ExprCoord<'src>
+where
+    F: Fold<'src> + ?Sized,
+{
+    ExprCoord::new(node.components.map(|c| f.fold_expr_coord_component(c)))
+}
+
+pub fn fold_expr_coord_component<'src, F>(
+    f: &mut F,
+    node: ExprCoordComponent<'src>,
+) -> ExprCoordComponent<'src>
+where
+    F: Fold<'src> + ?Sized,
+{
+    ExprCoordComponent::new(
+        f.fold_coord_kind(node.kind),
+        node.marker,
+        node.offset.map(|offset| f.fold_lit(offset)),
+    )
+}
+
+pub fn fold_expr_map<'src, F>(f: &mut F, node: ExprMap<'src>) -> ExprMap<'src>
+where
+    F: Fold<'src> + ?Sized,
+{
+    ExprMap::new(
+        node.curlies,
+        node.fields.map(|field| f.fold_expr_map_field(field)),
+    )
+}
+
+pub fn fold_expr_map_field<'src, F>(f: &mut F, node: ExprMapField<'src>) -> ExprMapField<'src>
+where
+    F: Fold<'src> + ?Sized,
+{
+    ExprMapField::new(
+        f.fold_lit_string(node.key),
+        node.colon,
+        f.fold_expr(node.value),
+    )
+}
+
+pub fn fold_table<'src, F>(
+    f: &mut F,
+    node: Table<'src, Ident<'src>>,
+) -> Table<'src, Ident<'src>>
+where
+    F: Fold<'src> + ?Sized,
+{
+    Table::new(
+        node.brackets,
+        node.fields.map(|field| f.fold_table_field(field)),
+    )
+}
+
+pub fn fold_table_field<'src, F>(
+    f: &mut F,
+    node: TableField<'src, Ident<'src>>,
+) -> TableField<'src, Ident<'src>>
+where
+    F: Fold<'src> + ?Sized,
+{
+    TableField::new(
+        f.fold_ident(node.key),
+        node.eq,
+        node.value.map(|value| f.fold_expr(value)),
+    )
+}
+
+pub fn fold_lit<'src, F>(f: &mut F, node: Lit<'src>) -> Lit<'src>
+where
+    F: Fold<'src> + ?Sized,
+{
+    match node {
+        Lit::Int(i) => Lit::Int(f.fold_lit_int(i)),
+        Lit::String(s) => Lit::String(f.fold_lit_string(s)),
+        Lit::Bool(b) => Lit::Bool(f.fold_lit_bool(b)),
+        Lit::Float(fl) => Lit::Float(f.fold_lit_float(fl)),
+        Lit::Path(p) => Lit::Path(f.fold_lit_path(p)),
+    }
+}
+
+#[test]
+fn folding_every_int_literal_reaches_nested_command_arguments() {
+    use crate::parser::Parse;
+    use crate::span::FileRef;
+    use crate::to_tokens::ToTokens;
+    use crate::token::{Kind, TokenIter};
+    use logos::Logos;
+
+    struct IncrementInts;
+
+    impl<'src> Fold<'src> for IncrementInts {
+        fn fold_lit_int(&mut self, node: LitInt) -> LitInt {
+            LitInt::new(node.value + 1, node.span)
+        }
+    }
+
+    let src = "/say 1 2 3";
+    let lex = Kind::lexer(src);
+    let mut tokens = TokenIter::new(lex, FileRef(0)).peekable();
+    let function = Function::parse(&mut tokens).unwrap();
+
+    let folded = IncrementInts.fold_function(function);
+
+    assert_eq!(folded.unparse(), "/say 2 3 4");
+}
@@ -0,0 +1,63 @@
+//! String interning for `Ident`/`Path` token text. Datapacks repeat the
+//! same resource paths and identifiers constantly, so following
+//! holey-bytes' use of [`lasso::Rodeo`] as a logos `extras` field, the
+//! lexer interns them through a shared [`Interner`] instead of letting
+//! every occurrence carry its own `&str`. Keyword/resource comparisons then
+//! become a cheap integer comparison on the resulting [`Sym`].
+
+use std::{cell::RefCell, rc::Rc};
+
+use lasso::{Rodeo, Spur};
+
+/// A handle into an [`Interner`], resolved back to text through
+/// [`Interner::resolve`].
+pub type Sym = Spur;
+
+/// One `Interner` is normally shared (via [`SharedInterner`]) across every
+/// file's lexer, so identical identifiers/paths intern to the same `Sym`
+/// regardless of which file they came from.
+#[derive(Debug, Default)]
+pub struct Interner {
+    rodeo: Rodeo,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Interns `text`, returning the same `Sym` for repeated occurrences of
+    /// identical text.
+    pub fn intern(&mut self, text: &str) -> Sym {
+        self.rodeo.get_or_intern(text)
+    }
+
+    /// Resolves `sym` back to the text it was interned from.
+    ///
+    /// Panics if `sym` was never produced by this `Interner`.
+    pub fn resolve(&self, sym: Sym) -> &str {
+        self.rodeo.resolve(&sym)
+    }
+}
+
+/// The logos `extras` type for [`crate::token::Kind`]: `Rc<RefCell<..>>`
+/// rather than a bare `Interner` because a single `Interner` is shared by
+/// several independent `Lexer`s (one per file), and logos extras are
+/// otherwise owned per-`Lexer`. Implements `Default` so `Kind::lexer` keeps
+/// working without callers having to thread an interner through; use
+/// `Kind::lexer_with_extras` with a clone of `SourceMap`'s interner to
+/// share one across files instead.
+pub type SharedInterner = Rc<RefCell<Interner>>;
+
+#[test]
+fn repeated_text_interns_to_the_same_symbol() {
+    let mut interner = Interner::new();
+    let a = interner.intern("minecraft:stone");
+    let b = interner.intern("minecraft:stone");
+    let c = interner.intern("minecraft:dirt");
+
+    assert_eq!(a, b);
+    assert_ne!(a, c);
+    assert_eq!(interner.resolve(a), "minecraft:stone");
+    assert_eq!(interner.resolve(c), "minecraft:dirt");
+}
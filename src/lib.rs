@@ -1,9 +1,16 @@
 pub mod ast;
 pub mod error;
+pub mod fold;
+pub mod intern;
 pub mod parser;
+pub mod source_map;
 pub mod span;
 pub mod test;
+pub mod to_tokens;
 pub mod token;
+pub mod token_tree;
+pub mod visit;
+pub mod visit_mut;
 
 pub fn add(left: u64, right: u64) -> u64 {
     left + right
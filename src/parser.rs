@@ -4,7 +4,7 @@ use logos::{Lexer, Logos};
 
 use crate::{
     ast::Function,
-    span::Span,
+    span::{FileRef, Span, Spanned},
     test::TEST_CMD,
     token::{Kind, LexError, Token, TokenIter},
 };
@@ -19,19 +19,234 @@ pub trait Parse<'src, T = Self> {
     fn parse(tokens: &mut Peekable<TokenIter<'src>>) -> ParseResult<'src, T>;
 }
 
+/// Reports whether a node could start parsing at the current position,
+/// without consuming any tokens. Used by combinators like `Separated` that
+/// need to decide whether "one more item" follows without committing to it,
+/// and by `Lookahead` to accumulate an expected-token set.
+pub trait Peek<'src> {
+    fn peek(tokens: &mut Peekable<TokenIter<'src>>) -> bool;
+
+    /// A representative `Kind` used to label this type in `Lookahead` error
+    /// messages. Only the discriminant is ever inspected, so any payload is
+    /// a throwaway placeholder.
+    fn example() -> Kind<'src>;
+}
+
+/// Borrowed from syn's `Lookahead1`: tries a sequence of `Peek` types
+/// against the current token without consuming it, recording each one that
+/// didn't match, so that if none do, `error()` can report the whole
+/// expected set instead of a bare "invalid token".
+pub struct Lookahead<'a, 'src> {
+    tokens: &'a mut Peekable<TokenIter<'src>>,
+    expected: ExpectedSet<'src>,
+}
+
+impl<'a, 'src> Lookahead<'a, 'src> {
+    pub fn new(tokens: &'a mut Peekable<TokenIter<'src>>) -> Self {
+        Self {
+            tokens,
+            expected: ExpectedSet::new(),
+        }
+    }
+
+    /// Peeks for `P`, recording it as an expected alternative if it doesn't
+    /// match. Never consumes a token.
+    pub fn peek<P: Peek<'src>>(&mut self) -> bool {
+        if P::peek(self.tokens) {
+            true
+        } else {
+            self.expected.push_kind(P::example());
+            false
+        }
+    }
+
+    /// Like [`Self::peek`], but records `label` (e.g. "a selector") instead
+    /// of `P::example()`'s bare `Kind`, for productions better described by
+    /// a higher-level name than their single leading token.
+    pub fn peek_labeled<P: Peek<'src>>(&mut self, label: &'static str) -> bool {
+        if P::peek(self.tokens) {
+            true
+        } else {
+            self.expected.push_label(label);
+            false
+        }
+    }
+
+    /// Turns the accumulated expected set into a `ParseError`, describing
+    /// whatever token is actually at the front of the stream.
+    pub fn error(self) -> ParseError<'src> {
+        match self.tokens.peek() {
+            Some(Ok(token)) => ParseError::Unexpected {
+                found: token.clone(),
+                expected: self.expected,
+            },
+            Some(Err(err)) => ParseError::LexError(err.clone()),
+            None => ParseError::Eof,
+        }
+    }
+}
+
+/// The alternatives a parser was looking for at some position: a mix of
+/// bare token `Kind`s (accumulated by `Lookahead` as it tries each
+/// alternative) and higher-level labels for productions that accept more
+/// than one shape, like "a coordinate" or "a selector".
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ExpectedSet<'src> {
+    kinds: Vec<Kind<'src>>,
+    labels: Vec<&'static str>,
+}
+
+impl<'src> ExpectedSet<'src> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// An expected set containing a single `Kind`.
+    pub fn of_kind(kind: Kind<'src>) -> Self {
+        let mut set = Self::new();
+        set.push_kind(kind);
+        set
+    }
+
+    /// An expected set built from several `Kind`s, e.g. the alternatives of
+    /// an `extract_token!` call.
+    pub fn of_kinds(kinds: impl IntoIterator<Item = Kind<'src>>) -> Self {
+        let mut set = Self::new();
+        set.kinds.extend(kinds);
+        set
+    }
+
+    /// An expected set containing a single higher-level label, such as "a
+    /// coordinate", for productions too broad to name as one `Kind`.
+    pub fn of_label(label: &'static str) -> Self {
+        let mut set = Self::new();
+        set.push_label(label);
+        set
+    }
+
+    pub fn push_kind(&mut self, kind: Kind<'src>) {
+        self.kinds.push(kind);
+    }
+
+    pub fn push_label(&mut self, label: &'static str) {
+        self.labels.push(label);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.kinds.is_empty() && self.labels.is_empty()
+    }
+
+    /// Whether this set names nothing but a bare identifier, i.e. the
+    /// position required an `Ident` and nothing else. Used by
+    /// `ParseError::kind` to classify such errors as `ErrorKind::InvalidIdentifier`.
+    fn is_identifier_only(&self) -> bool {
+        self.labels.is_empty() && matches!(self.kinds.as_slice(), [Kind::Ident(_)])
+    }
+}
+
+impl<'src> std::fmt::Display for ExpectedSet<'src> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let items: Vec<&str> = self
+            .kinds
+            .iter()
+            .map(Kind::describe)
+            .chain(self.labels.iter().copied())
+            .collect();
+
+        match items.as_slice() {
+            [] => write!(f, "more input"),
+            [only] => write!(f, "{only}"),
+            [init @ .., last] => {
+                for (i, item) in init.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                write!(f, ", or {last}")
+            }
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum ParseError<'src> {
     LexError(LexError),
-    // TODO: add a method to notate what kind of token was expected
-    InvalidToken(Token<'src>),
+    /// The parser found `found` where one of `expected` was required. An
+    /// empty `expected` set means no single alternative was tracked; the
+    /// token itself was simply out of place.
+    Unexpected {
+        found: Token<'src>,
+        expected: ExpectedSet<'src>,
+    },
+    /// Produced by [`crate::token_tree`] when a `{`/`[` is never closed, or
+    /// is closed by the wrong delimiter. `open` points at the opener.
+    UnbalancedDelimiter {
+        open: Span,
+        expected: Kind<'src>,
+    },
     // TODO: Make spesific errors like:
     // "x" is not a valid number
     // based off in-game errors
+    /// The token stream ran out where another token was required. Carries no
+    /// span: the stream is exhausted, not pointing at a bad token. Callers
+    /// that need a position for this case (recovery, diagnostics) have to
+    /// fall back to something they already know, e.g. `ast::error_span`
+    /// falling back to the start of the statement that was being parsed.
     Eof,
 }
 
 pub type ParseResult<'src, T> = Result<T, ParseError<'src>>;
 
+/// A coarse category for a `ParseError`, for callers (e.g. diagnostics or
+/// recovery code) that care more about *why* parsing failed than the exact
+/// variant. Modeled on AbleScript's `ErrorKind` split.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The token stream ran out before a required token appeared.
+    EndOfTokenStream,
+    /// An identifier was required but something else was found.
+    InvalidIdentifier,
+    /// Any other syntax error.
+    Syntax,
+}
+
+impl<'src> ParseError<'src> {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            ParseError::Eof => ErrorKind::EndOfTokenStream,
+            ParseError::Unexpected { expected, .. } if expected.is_identifier_only() => {
+                ErrorKind::InvalidIdentifier
+            }
+            ParseError::Unexpected { .. } | ParseError::UnbalancedDelimiter { .. } => {
+                ErrorKind::Syntax
+            }
+            ParseError::LexError(_) => ErrorKind::Syntax,
+        }
+    }
+}
+
+impl<'src> std::fmt::Display for ParseError<'src> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::LexError(e) => write!(f, "{e}"),
+            ParseError::Unexpected { found, expected } => {
+                if expected.is_empty() {
+                    write!(f, "unexpected {}", found.kind.describe())
+                } else {
+                    write!(f, "expected {expected}, found {}", found.kind.describe())
+                }
+            }
+            ParseError::UnbalancedDelimiter { open: _, expected } => {
+                write!(f, "unclosed delimiter, expected {}", expected.describe())
+            }
+            ParseError::Eof => write!(f, "unexpected end of input"),
+        }
+    }
+}
+
+impl<'src> std::error::Error for ParseError<'src> {}
+
 impl<'src> CommandParser<'src> {
     fn new(lexer: Lexer<'src, Kind<'src>>, src: &'src str) -> Self {
         Self {
@@ -42,10 +257,78 @@ impl<'src> CommandParser<'src> {
     }
 }
 
+/// Consumes the next token and requires it to match `kind` (compared by
+/// discriminant only; any payload on `kind` is just a throwaway
+/// placeholder), the single-token counterpart to `extract_token!`'s
+/// alternation. `CommandParser` wraps a `logos::SpannedIter` rather than a
+/// `Peekable<TokenIter>`, so this is written against the token stream the
+/// rest of the parser already uses, not as a `CommandParser` method.
+pub fn expect<'src>(
+    tokens: &mut Peekable<TokenIter<'src>>,
+    kind: Kind<'src>,
+) -> ParseResult<'src, Token<'src>> {
+    match tokens.next() {
+        Some(Ok(tok)) if std::mem::discriminant(&tok.kind) == std::mem::discriminant(&kind) => {
+            Ok(tok)
+        }
+        Some(Ok(tok)) => Err(ParseError::Unexpected {
+            found: tok,
+            expected: ExpectedSet::of_kind(kind),
+        }),
+        Some(Err(e)) => Err(ParseError::LexError(e)),
+        None => Err(ParseError::Eof),
+    }
+}
+
+/// Skips tokens until one matching a `Kind` in `sync` (compared by
+/// discriminant) is next, or the stream is exhausted, without consuming the
+/// synchronizing token itself, so multiple errors can be collected instead
+/// of bailing out on the first. Lex errors encountered along the way are
+/// appended to `errors` rather than silently dropped. `already_reported` is
+/// the span of the error that triggered recovery; most of this crate's
+/// parse functions report a `LexError` by peeking rather than consuming, so
+/// that same token is often still sitting at the front of the stream here,
+/// and it must not be reported twice. Returns the end of the skipped range.
+pub fn recover_to<'src>(
+    tokens: &mut Peekable<TokenIter<'src>>,
+    sync: &[Kind<'src>],
+    already_reported: &Span,
+    errors: &mut Vec<ParseError<'src>>,
+) -> usize {
+    let is_sync = |kind: &Kind<'src>| {
+        sync.iter()
+            .any(|s| std::mem::discriminant(s) == std::mem::discriminant(kind))
+    };
+
+    let mut end = already_reported.end;
+    let mut first = true;
+
+    loop {
+        match tokens.peek() {
+            Some(Ok(token)) if is_sync(&token.kind) => break,
+            Some(Ok(_)) => {
+                let token = tokens.next().unwrap().unwrap();
+                end = token.span.end;
+            }
+            Some(Err(_)) => {
+                let err = tokens.next().unwrap().unwrap_err();
+                end = err.span().end;
+                if !(first && err.span() == *already_reported) {
+                    errors.push(ParseError::LexError(err));
+                }
+            }
+            None => break,
+        }
+        first = false;
+    }
+
+    end
+}
+
 #[test]
 fn test_parser() {
     let lex = Kind::lexer(TEST_CMD);
 
-    let mut tokens = TokenIter::new(lex).peekable();
+    let mut tokens = TokenIter::new(lex, FileRef(0)).peekable();
     dbg!(Function::parse(&mut tokens));
 }
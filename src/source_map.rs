@@ -0,0 +1,73 @@
+//! Owns the text of every loaded datapack file and hands out [`FileRef`]
+//! handles, so a [`Span`] produced while parsing one file can be resolved
+//! back to a `(path, line, column)` location even when it's reported
+//! alongside a span from a different file. Also owns the [`Interner`]
+//! shared by every file's lexer, so an `Ident`/`Path` repeated across
+//! several files still interns to the same symbol.
+
+use std::path::{Path, PathBuf};
+
+use crate::{
+    intern::SharedInterner,
+    span::{FileRef, Span},
+};
+
+struct SourceFile {
+    path: PathBuf,
+    text: String,
+}
+
+#[derive(Default)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+    interner: SharedInterner,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a loaded file's text and returns the handle future
+    /// `Token`s and `LexError`s from it should carry.
+    pub fn add_file(&mut self, path: PathBuf, text: String) -> FileRef {
+        self.files.push(SourceFile { path, text });
+        FileRef(self.files.len() - 1)
+    }
+
+    /// The `Interner` shared by every file registered with this map. Pass a
+    /// clone of this to `Kind::lexer_with_extras` when lexing one of this
+    /// map's files, so its identifiers/paths dedupe against every other
+    /// file's.
+    pub fn interner(&self) -> SharedInterner {
+        self.interner.clone()
+    }
+
+    pub fn path(&self, file: FileRef) -> &Path {
+        &self.files[file.0].path
+    }
+
+    pub fn text(&self, file: FileRef) -> &str {
+        &self.files[file.0].text
+    }
+
+    /// Resolves a byte-offset span back to a 1-indexed `(path, line,
+    /// column)` location, by counting newlines up to `span.start`.
+    pub fn resolve(&self, file: FileRef, span: &Span) -> (&Path, usize, usize) {
+        let source = self.text(file);
+        let offset = span.start.min(source.len());
+
+        let mut line = 1;
+        let mut col = 1;
+        for ch in source[..offset].chars() {
+            if ch == '\n' {
+                line += 1;
+                col = 1;
+            } else {
+                col += 1;
+            }
+        }
+
+        (self.path(file), line, col)
+    }
+}
@@ -25,3 +25,55 @@ impl From<Range<usize>> for Span {
 pub trait Spanned {
     fn span(&self) -> Span;
 }
+
+/// A handle into a [`crate::source_map::SourceMap`], identifying which
+/// loaded file a span belongs to. Cheap to copy and compare; the map itself
+/// owns the actual path and text.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FileRef(pub usize);
+
+impl FileRef {
+    /// Used when reconstructing tokens that were never associated with a
+    /// loaded file, e.g. [`crate::to_tokens`] rebuilding a token purely to
+    /// reprint it to a string.
+    pub const SYNTHETIC: FileRef = FileRef(usize::MAX);
+}
+
+/// Extends `Spanned` with the file a span belongs to, so a diagnostic can
+/// resolve a location back to `(path, line, column)` through a
+/// `SourceMap` even when it points into a different file than the one
+/// currently being parsed.
+pub trait Location: Spanned {
+    fn file(&self) -> FileRef;
+}
+
+/// A value paired with the span and file it came from, modeled on the
+/// token wrapper in the `matzo` lexer. `Token` and `LexError` carry their
+/// `file` as a field directly rather than wrapping in `Located`, since
+/// their other fields are already pattern-matched throughout the parser;
+/// `Located` is for attaching file info to a value without defining a
+/// bespoke struct for it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Located<T> {
+    pub item: T,
+    pub span: Span,
+    pub file: FileRef,
+}
+
+impl<T> Located<T> {
+    pub fn new(item: T, span: Span, file: FileRef) -> Self {
+        Self { item, span, file }
+    }
+}
+
+impl<T> Spanned for Located<T> {
+    fn span(&self) -> Span {
+        self.span.clone()
+    }
+}
+
+impl<T> Location for Located<T> {
+    fn file(&self) -> FileRef {
+        self.file
+    }
+}
@@ -0,0 +1,431 @@
+//! Reprints a parsed tree back to source text. Modeled on rune's `ToTokens`:
+//! every node knows how to push its constituent [`Token`]s onto a buffer in
+//! order, reusing the original tokens (and therefore spans) wherever the
+//! node already stores one. Literals and comments are rebuilt from their
+//! parsed values instead, so a tree that hasn't been touched by
+//! [`crate::fold`] reprints verbatim except that a run of original
+//! inter-token whitespace collapses to a single space, numbers print in
+//! canonical form (`.5` as `0.5`, `007` as `7`), string literals are
+//! re-quoted with their escapes re-encoded (a unicode escape reprints as
+//! the character it encodes), a comment loses the whitespace and extra `#`s
+//! around its text, and a `§` formatting code loses the character after the
+//! `§`, which the lexer does not keep. `unparse` then joins the buffer back
+//! into a string, inserting that single space wherever the
+//! `Span`s of two adjacent tokens show a gap between them, or (for tokens
+//! rebuilt with no original span to compare, e.g. a hand-assembled tree)
+//! wherever two adjacent tokens would otherwise lex as one (e.g. two
+//! integers).
+
+use std::borrow::Cow;
+
+use crate::ast::{
+    Comma, Expr, ExprBinary, ExprCoord, ExprCoordComponent, ExprMap, ExprMapField, ExprOperator,
+    ExprRange, ExprTarget, ExprUrnary, Function, Ident, Lit, LitBool, LitFloat, LitInt, LitPath,
+    LitString, Operator, Separated, Stmt, StmtComment, StmtCommand, Table, TableField, UnOp,
+};
+use crate::intern::Sym;
+use crate::span::{FileRef, Span};
+use crate::token::{Interned, Kind, Token};
+
+pub trait ToTokens<'src> {
+    fn to_tokens(&self, out: &mut Vec<Token<'src>>);
+
+    fn unparse(&self) -> String {
+        let mut tokens = Vec::new();
+        self.to_tokens(&mut tokens);
+        render(&tokens)
+    }
+}
+
+/// Recovers the original `'src`-lifetime string out of a `Cow`. For a
+/// `Borrowed` value (every node that came straight out of the parser) this
+/// is free. A `Fold` pass can replace a node's `Cow` with owned, generated
+/// text, which has no connection to `'src` at all; we leak it to get a
+/// `'static` (and therefore valid-for-`'src`) string. Every call leaks a
+/// fresh copy, so each `to_tokens`/`unparse` of an edited tree leaks the
+/// text of its owned values again.
+fn cow_to_src<'src>(cow: &Cow<'src, str>) -> &'src str {
+    match cow {
+        Cow::Borrowed(s) => s,
+        Cow::Owned(s) => Box::leak(s.clone().into_boxed_str()),
+    }
+}
+
+fn operator_kind<'src>(op: &Operator) -> Kind<'src> {
+    match op {
+        Operator::Equal => Kind::Equal,
+        Operator::AddAssign => Kind::AddAssign,
+        Operator::SubAssign => Kind::SubAssign,
+        Operator::MulAssign => Kind::MulAssign,
+        Operator::DivAssign => Kind::DivAssign,
+        Operator::Gt => Kind::Gt,
+        Operator::Lt => Kind::Lt,
+        Operator::Wildcard => Kind::Wildcard,
+    }
+}
+
+impl<'src> ToTokens<'src> for Token<'src> {
+    fn to_tokens(&self, out: &mut Vec<Token<'src>>) {
+        out.push(self.clone());
+    }
+}
+
+impl<'src> ToTokens<'src> for Comma<'src> {
+    fn to_tokens(&self, out: &mut Vec<Token<'src>>) {
+        out.push(self.0.clone());
+    }
+}
+
+impl<'src> ToTokens<'src> for UnOp<'src> {
+    fn to_tokens(&self, out: &mut Vec<Token<'src>>) {
+        match self {
+            UnOp::Not(t) => out.push(t.clone()),
+            UnOp::FormatSelection(t) => out.push(t.clone()),
+        }
+    }
+}
+
+impl<'src, T, Sep, const IS_TRAILING: bool> ToTokens<'src> for Separated<T, Sep, IS_TRAILING>
+where
+    T: ToTokens<'src>,
+    Sep: ToTokens<'src>,
+{
+    fn to_tokens(&self, out: &mut Vec<Token<'src>>) {
+        for (i, value) in self.iter().enumerate() {
+            value.to_tokens(out);
+            if let Some(sep) = self.separators().get(i) {
+                sep.to_tokens(out);
+            }
+        }
+    }
+}
+
+impl<'src> ToTokens<'src> for Function<'src> {
+    fn to_tokens(&self, out: &mut Vec<Token<'src>>) {
+        for (i, stmt) in self.statements.iter().enumerate() {
+            if i > 0 {
+                out.push(Token::new(Kind::LineBreak, Span::new(0, 0), FileRef::SYNTHETIC));
+            }
+            stmt.to_tokens(out);
+        }
+    }
+}
+
+impl<'src> ToTokens<'src> for Stmt<'src> {
+    fn to_tokens(&self, out: &mut Vec<Token<'src>>) {
+        match self {
+            Stmt::Command(c) => c.to_tokens(out),
+            Stmt::Comment(c) => c.to_tokens(out),
+            // The tokens this placeholder covered were never collected, so
+            // there is nothing to reprint; it emits no tokens.
+            Stmt::Error(_) => {}
+        }
+    }
+}
+
+impl<'src> ToTokens<'src> for StmtCommand<'src> {
+    fn to_tokens(&self, out: &mut Vec<Token<'src>>) {
+        if let Some(slash) = &self.slash {
+            out.push(slash.clone());
+        }
+        self.ident.to_tokens(out);
+        if let Some(arguments) = &self.arguments {
+            arguments.to_tokens(out);
+        }
+    }
+}
+
+impl<'src> ToTokens<'src> for StmtComment<'src> {
+    fn to_tokens(&self, out: &mut Vec<Token<'src>>) {
+        out.push(Token::new(
+            Kind::Comment(cow_to_src(&self.value)),
+            self.span.clone(),
+            FileRef::SYNTHETIC,
+        ));
+    }
+}
+
+impl<'src> ToTokens<'src> for Expr<'src> {
+    fn to_tokens(&self, out: &mut Vec<Token<'src>>) {
+        match self {
+            Expr::Lit(lit) => lit.to_tokens(out),
+            Expr::Urnary(u) => u.to_tokens(out),
+            Expr::Range(r) => r.to_tokens(out),
+            Expr::Map(m) => m.to_tokens(out),
+            Expr::Target(t) => t.to_tokens(out),
+            Expr::Binary(b) => b.to_tokens(out),
+            Expr::Coord(c) => c.to_tokens(out),
+        }
+    }
+}
+
+impl<'src> ToTokens<'src> for ExprBinary<'src> {
+    fn to_tokens(&self, out: &mut Vec<Token<'src>>) {
+        self.lhs.to_tokens(out);
+        self.op.to_tokens(out);
+        self.rhs.to_tokens(out);
+    }
+}
+
+impl<'src> ToTokens<'src> for ExprOperator {
+    fn to_tokens(&self, out: &mut Vec<Token<'src>>) {
+        out.push(Token::new(operator_kind(&self.value), self.span.clone(), FileRef::SYNTHETIC));
+    }
+}
+
+impl<'src> ToTokens<'src> for ExprTarget<'src> {
+    fn to_tokens(&self, out: &mut Vec<Token<'src>>) {
+        out.push(self.select.clone());
+        self.target.to_tokens(out);
+        if let Some(params) = &self.params {
+            params.to_tokens(out);
+        }
+    }
+}
+
+impl<'src> ToTokens<'src> for ExprRange<'src> {
+    fn to_tokens(&self, out: &mut Vec<Token<'src>>) {
+        if let Some(start) = &self.start {
+            start.to_tokens(out);
+        }
+        out.push(self.limit.clone());
+        if let Some(end) = &self.end {
+            end.to_tokens(out);
+        }
+    }
+}
+
+impl<'src> ToTokens<'src> for ExprUrnary<'src> {
+    fn to_tokens(&self, out: &mut Vec<Token<'src>>) {
+        self.op.to_tokens(out);
+        if let Some(expr) = &self.expr {
+            expr.to_tokens(out);
+        }
+    }
+}
+
+impl<'src> ToTokens<'src> for ExprCoord<'src> {
+    fn to_tokens(&self, out: &mut Vec<Token<'src>>) {
+        for component in &self.components {
+            component.to_tokens(out);
+        }
+    }
+}
+
+impl<'src> ToTokens<'src> for ExprCoordComponent<'src> {
+    fn to_tokens(&self, out: &mut Vec<Token<'src>>) {
+        if let Some(marker) = &self.marker {
+            out.push(marker.clone());
+        }
+        if let Some(offset) = &self.offset {
+            offset.to_tokens(out);
+        }
+    }
+}
+
+impl<'src> ToTokens<'src> for ExprMap<'src> {
+    fn to_tokens(&self, out: &mut Vec<Token<'src>>) {
+        out.push(self.curlies.0.clone());
+        self.fields.to_tokens(out);
+        out.push(self.curlies.1.clone());
+    }
+}
+
+impl<'src> ToTokens<'src> for ExprMapField<'src> {
+    fn to_tokens(&self, out: &mut Vec<Token<'src>>) {
+        self.key.to_tokens(out);
+        out.push(self.colon.clone());
+        self.value.to_tokens(out);
+    }
+}
+
+impl<'src> ToTokens<'src> for Table<'src, Ident<'src>> {
+    fn to_tokens(&self, out: &mut Vec<Token<'src>>) {
+        out.push(self.brackets.0.clone());
+        self.fields.to_tokens(out);
+        out.push(self.brackets.1.clone());
+    }
+}
+
+impl<'src> ToTokens<'src> for TableField<'src, Ident<'src>> {
+    fn to_tokens(&self, out: &mut Vec<Token<'src>>) {
+        self.key.to_tokens(out);
+        out.push(self.eq.clone());
+        if let Some(value) = &self.value {
+            value.to_tokens(out);
+        }
+    }
+}
+
+impl<'src> ToTokens<'src> for Lit<'src> {
+    fn to_tokens(&self, out: &mut Vec<Token<'src>>) {
+        match self {
+            Lit::Int(i) => i.to_tokens(out),
+            Lit::String(s) => s.to_tokens(out),
+            Lit::Bool(b) => b.to_tokens(out),
+            Lit::Float(f) => f.to_tokens(out),
+            Lit::Path(p) => p.to_tokens(out),
+        }
+    }
+}
+
+impl<'src> ToTokens<'src> for LitInt {
+    fn to_tokens(&self, out: &mut Vec<Token<'src>>) {
+        out.push(Token::new(Kind::Int(self.value), self.span.clone(), FileRef::SYNTHETIC));
+    }
+}
+
+impl<'src> ToTokens<'src> for LitFloat {
+    fn to_tokens(&self, out: &mut Vec<Token<'src>>) {
+        out.push(Token::new(Kind::Float(self.value), self.span.clone(), FileRef::SYNTHETIC));
+    }
+}
+
+impl<'src> ToTokens<'src> for LitBool {
+    fn to_tokens(&self, out: &mut Vec<Token<'src>>) {
+        out.push(Token::new(Kind::Bool(self.value), self.span.clone(), FileRef::SYNTHETIC));
+    }
+}
+
+impl<'src> ToTokens<'src> for LitString<'src> {
+    fn to_tokens(&self, out: &mut Vec<Token<'src>>) {
+        out.push(Token::new(Kind::String(self.value.clone()), self.span.clone(), FileRef::SYNTHETIC));
+    }
+}
+
+impl<'src> ToTokens<'src> for LitPath<'src> {
+    fn to_tokens(&self, out: &mut Vec<Token<'src>>) {
+        out.push(Token::new(
+            Kind::Path(synthetic_interned(cow_to_src(&self.value))),
+            self.span.clone(),
+            FileRef::SYNTHETIC,
+        ));
+    }
+}
+
+impl<'src> ToTokens<'src> for Ident<'src> {
+    fn to_tokens(&self, out: &mut Vec<Token<'src>>) {
+        out.push(Token::new(
+            Kind::Ident(synthetic_interned(cow_to_src(&self.value))),
+            self.span.clone(),
+            FileRef::SYNTHETIC,
+        ));
+    }
+}
+
+/// Builds an `Interned` payload for a token reprinted straight from an AST
+/// node rather than lexed. There's no `Interner` to consult here, so the
+/// `Sym` is just a placeholder; `render_kind` only ever reads `text`.
+fn synthetic_interned(text: &str) -> Interned<'_> {
+    Interned {
+        sym: Sym::default(),
+        text,
+    }
+}
+
+/// Whether `kind` is one of the "bare word" token kinds that would merge
+/// with an adjacent one of the same family if printed back-to-back (e.g.
+/// `1` and `2` becoming `12`). Punctuation always prints tight against its
+/// neighbors, matching how this grammar is normally written by hand.
+fn is_word_like(kind: &Kind<'_>) -> bool {
+    matches!(
+        kind,
+        Kind::Ident(_) | Kind::Int(_) | Kind::Float(_) | Kind::Bool(_) | Kind::Path(_)
+    )
+}
+
+fn render_kind(kind: &Kind<'_>) -> String {
+    match kind {
+        Kind::Eof => String::new(),
+        Kind::FormatSelection => "§".to_string(),
+        Kind::Float(v) => v.to_string(),
+        Kind::Int(v) => v.to_string(),
+        Kind::String(s) => {
+            let mut quoted = String::from('"');
+            for c in s.chars() {
+                match c {
+                    '"' | '\\' => quoted.extend(['\\', c]),
+                    '\n' => quoted.push_str("\\n"),
+                    '\t' => quoted.push_str("\\t"),
+                    '\r' => quoted.push_str("\\r"),
+                    c => quoted.push(c),
+                }
+            }
+            quoted.push('"');
+            quoted
+        }
+        Kind::Ident(interned) => interned.text.to_string(),
+        Kind::Path(interned) => interned.text.to_string(),
+        Kind::Slash => "/".to_string(),
+        Kind::RightBrace => "}".to_string(),
+        Kind::LeftBrace => "{".to_string(),
+        Kind::LeftBracket => "[".to_string(),
+        Kind::RightBracket => "]".to_string(),
+        Kind::Selector => "@".to_string(),
+        Kind::Comma => ",".to_string(),
+        Kind::Neg => "-".to_string(),
+        Kind::Not => "!".to_string(),
+        Kind::Limit => "..".to_string(),
+        Kind::Assign => "=".to_string(),
+        Kind::Equal => "<>".to_string(),
+        Kind::AddAssign => "+=".to_string(),
+        Kind::SubAssign => "-=".to_string(),
+        Kind::MulAssign => "*=".to_string(),
+        Kind::DivAssign => "/=".to_string(),
+        Kind::Gt => ">".to_string(),
+        Kind::Lt => "<".to_string(),
+        Kind::Wildcard => "*".to_string(),
+        Kind::Bool(b) => b.to_string(),
+        Kind::RelativeCoordinate => "~".to_string(),
+        Kind::LocalCoordinate => "^".to_string(),
+        Kind::Comment(s) => format!("#{s}"),
+        Kind::LineBreak => "\n".to_string(),
+        Kind::Colon => ":".to_string(),
+    }
+}
+
+/// Whether a space should separate `prev` from `token`: either they were
+/// genuinely apart in the source (a gap between `prev`'s span and `token`'s,
+/// the way a `Fold`-untouched node still carries its original span even
+/// when its rebuilt `Token` is marked `FileRef::SYNTHETIC`), or omitting one
+/// would merge them into a single token on re-lexing (the `is_word_like`
+/// case, needed for nodes built with no original span to compare, e.g. a
+/// tree assembled entirely by hand). `Kind::LineBreak` already separates
+/// visually, so it never asks for an extra leading space.
+fn needs_space(prev: &Token<'_>, token: &Token<'_>) -> bool {
+    !matches!(prev.kind, Kind::LineBreak)
+        && (token.span.start > prev.span.end || (is_word_like(&prev.kind) && is_word_like(&token.kind)))
+}
+
+fn render(tokens: &[Token<'_>]) -> String {
+    let mut out = String::new();
+    let mut prev: Option<&Token<'_>> = None;
+
+    for token in tokens {
+        if let Some(prev_tok) = prev
+            && needs_space(prev_tok, token)
+        {
+            out.push(' ');
+        }
+        out.push_str(&render_kind(&token.kind));
+        prev = Some(token);
+    }
+
+    out
+}
+
+#[test]
+fn unparse_preserves_whitespace_between_unedited_tokens() {
+    use crate::ast::Function;
+    use crate::parser::Parse;
+    use crate::span::FileRef;
+    use crate::token::TokenIter;
+    use logos::Logos;
+
+    let src = "/tp @a ~ ~1 ~-2";
+    let lex = Kind::lexer(src);
+    let mut tokens = TokenIter::new(lex, FileRef(0)).peekable();
+    let function = Function::parse(&mut tokens).unwrap();
+
+    assert_eq!(function.unparse(), src);
+}
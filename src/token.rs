@@ -1,14 +1,15 @@
 use std::{
+    borrow::Cow,
     marker::PhantomData,
     path::{Path, PathBuf},
 };
 
-use logos::Logos;
-use serde::{Deserialize, Serialize};
+use logos::{Lexer, Logos};
 
 use crate::{
     error::Error,
-    span::{Span, Spanned},
+    intern::{SharedInterner, Sym},
+    span::{FileRef, Location, Span, Spanned},
     test::TEST_CMD,
 };
 
@@ -16,11 +17,12 @@ use crate::{
 pub struct Token<'src> {
     pub kind: Kind<'src>,
     pub span: Span,
+    pub file: FileRef,
 }
 
 impl<'src> Token<'src> {
-    pub fn new(kind: Kind<'src>, span: Span) -> Self {
-        Self { kind, span }
+    pub fn new(kind: Kind<'src>, span: Span, file: FileRef) -> Self {
+        Self { kind, span, file }
     }
 }
 
@@ -30,6 +32,12 @@ impl<'src> Spanned for Token<'src> {
     }
 }
 
+impl<'src> Location for Token<'src> {
+    fn file(&self) -> FileRef {
+        self.file
+    }
+}
+
 #[derive(PartialEq, Debug, Clone, Default)]
 pub enum LexErrorItem {
     InvalidFloat(std::num::ParseFloatError),
@@ -38,6 +46,8 @@ pub enum LexErrorItem {
 
     InvalidBool(std::str::ParseBoolError),
 
+    InvalidEscape,
+
     #[default]
     Unknown,
 }
@@ -45,12 +55,13 @@ pub enum LexErrorItem {
 #[derive(PartialEq, Debug, Clone)]
 pub struct LexError {
     span: Span,
+    file: FileRef,
     err: LexErrorItem,
 }
 
 impl LexError {
-    pub fn new(err: LexErrorItem, span: Span) -> Self {
-        Self { span, err }
+    pub fn new(err: LexErrorItem, span: Span, file: FileRef) -> Self {
+        Self { span, file, err }
     }
 }
 
@@ -60,6 +71,12 @@ impl Spanned for LexError {
     }
 }
 
+impl Location for LexError {
+    fn file(&self) -> FileRef {
+        self.file
+    }
+}
+
 impl From<std::num::ParseFloatError> for LexErrorItem {
     fn from(value: std::num::ParseFloatError) -> Self {
         Self::InvalidFloat(value)
@@ -84,6 +101,7 @@ impl std::fmt::Display for LexErrorItem {
             LexErrorItem::InvalidFloat(e) => write!(f, "{e}"),
             LexErrorItem::InvalidInt(e) => write!(f, "{e}"),
             LexErrorItem::InvalidBool(e) => write!(f, "{e}"),
+            LexErrorItem::InvalidEscape => write!(f, "invalid escape sequence in string literal"),
 
             LexErrorItem::Unknown => write!(f, "Unknown Error"),
         }
@@ -92,8 +110,20 @@ impl std::fmt::Display for LexErrorItem {
 
 impl std::error::Error for LexErrorItem {}
 
-#[derive(Logos, Debug, PartialEq, Clone, Default, Copy, Serialize, Deserialize)]
-#[logos(skip r"[ \t\r]+", error = LexErrorItem)] // Ignore this regex pattern between tokens
+impl std::fmt::Display for LexError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.err)
+    }
+}
+
+impl std::error::Error for LexError {}
+
+#[derive(Logos, Debug, PartialEq, Clone, Default)]
+#[logos(
+    skip r"[ \t\r]+", // Ignore this regex pattern between tokens
+    error = LexErrorItem,
+    extras = SharedInterner
+)]
 pub enum Kind<'src> {
     #[default]
     Eof,
@@ -106,14 +136,14 @@ pub enum Kind<'src> {
     #[regex("-?[0-9]+", |lex| lex.slice().parse(),  priority=3,)]
     Int(i32),
 
-    #[regex("\"[^\"]+\"")]
-    String(&'src str),
+    #[regex(r#""([^"\\]|\\.)*""#, unescape_string)]
+    String(Cow<'src, str>),
 
-    #[regex("[a-z_.A-Z0-9]+")]
-    Ident(&'src str),
+    #[regex("[a-z_.A-Z0-9]+", intern)]
+    Ident(Interned<'src>),
 
-    #[regex("[a-z_:.A-Z0-9]+/[a-z_:.A-Z0-9/]+", priority = 1)]
-    Path(&'src str),
+    #[regex("[a-z_:.A-Z0-9]+/[a-z_:.A-Z0-9/]+", priority = 1, callback = intern)]
+    Path(Interned<'src>),
 
     #[token("/")]
     Slash,
@@ -192,13 +222,128 @@ pub enum Kind<'src> {
     Colon,
 }
 
+/// Decodes the backslash escapes inside a quoted string token. `lex.slice()`
+/// still includes the surrounding quotes, which are simply trimmed off.
+/// Strings with no backslash are returned borrowed from the source; only
+/// strings that actually need decoding pay for an allocation.
+fn unescape_string<'src>(lex: &mut Lexer<'src, Kind<'src>>) -> Result<Cow<'src, str>, LexErrorItem> {
+    let slice = lex.slice();
+    let inner = &slice[1..slice.len() - 1];
+
+    if !inner.contains('\\') {
+        return Ok(Cow::Borrowed(inner));
+    }
+
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('r') => out.push('\r'),
+            Some('"') => out.push('"'),
+            Some('\\') => out.push('\\'),
+            Some('u') => {
+                let hex: String = chars.by_ref().take(4).collect();
+                if hex.len() != 4 {
+                    return Err(LexErrorItem::InvalidEscape);
+                }
+                let code = u32::from_str_radix(&hex, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or(LexErrorItem::InvalidEscape)?;
+                out.push(code);
+            }
+            _ => return Err(LexErrorItem::InvalidEscape),
+        }
+    }
+
+    Ok(Cow::Owned(out))
+}
+
+/// An interned `Ident`/`Path` token payload: a cheap [`Sym`] handle for
+/// comparison, plus the original source slice it was interned from. Keeping
+/// the slice alongside the `Sym` means error messages and
+/// [`crate::to_tokens`] can recover the exact text without needing access to
+/// the `Interner` that produced the symbol.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Interned<'src> {
+    pub sym: Sym,
+    pub text: &'src str,
+}
+
+/// Interns the matched slice through the lexer's shared `Interner`
+/// (`lex.extras`), pairing the resulting `Sym` with the original slice.
+fn intern<'src>(lex: &mut Lexer<'src, Kind<'src>>) -> Interned<'src> {
+    let text = lex.slice();
+    let sym = lex.extras.borrow_mut().intern(text);
+    Interned { sym, text }
+}
+
+impl<'src> Kind<'src> {
+    /// A short, human-readable label for this kind, used to render
+    /// `ParseError::Unexpected` messages such as "expected `@`, an
+    /// identifier, or an integer, found `,`". Only the discriminant matters, so
+    /// this ignores any payload the variant carries.
+    pub fn describe(&self) -> &'static str {
+        match self {
+            Kind::Eof => "end of input",
+            Kind::FormatSelection => "`§`",
+            Kind::Float(_) => "a float",
+            Kind::Int(_) => "an integer",
+            Kind::String(_) => "a string",
+            Kind::Ident(_) => "an identifier",
+            Kind::Path(_) => "a path",
+            Kind::Slash => "`/`",
+            Kind::RightBrace => "`}`",
+            Kind::LeftBrace => "`{`",
+            Kind::LeftBracket => "`[`",
+            Kind::RightBracket => "`]`",
+            Kind::Selector => "`@`",
+            Kind::Comma => "`,`",
+            Kind::Neg => "`-`",
+            Kind::Not => "`!`",
+            Kind::Limit => "`..`",
+            Kind::Assign => "`=`",
+            Kind::Equal => "`<>`",
+            Kind::AddAssign => "`+=`",
+            Kind::SubAssign => "`-=`",
+            Kind::MulAssign => "`*=`",
+            Kind::DivAssign => "`/=`",
+            Kind::Gt => "`>`",
+            Kind::Lt => "`<`",
+            Kind::Wildcard => "`*`",
+            Kind::Bool(_) => "a boolean",
+            Kind::RelativeCoordinate => "`~`",
+            Kind::LocalCoordinate => "`^`",
+            Kind::Comment(_) => "a comment",
+            Kind::LineBreak => "a line break",
+            Kind::Colon => "`:`",
+        }
+    }
+}
+
 pub struct TokenIter<'src> {
     lex: logos::SpannedIter<'src, Kind<'src>>,
+    file: FileRef,
 }
 
 impl<'src> TokenIter<'src> {
-    pub fn new(lex: logos::Lexer<'src, Kind<'src>>) -> Self {
-        Self { lex: lex.spanned() }
+    /// `file` identifies which `SourceMap` entry this token stream was
+    /// lexed from, so every `Token`/`LexError` it yields can be traced back
+    /// to a `(path, line, column)` even once it's been stored away in an
+    /// AST node or error far from the original lexer.
+    pub fn new(lex: logos::Lexer<'src, Kind<'src>>, file: FileRef) -> Self {
+        Self {
+            lex: lex.spanned(),
+            file,
+        }
     }
 }
 
@@ -210,8 +355,8 @@ impl<'src> Iterator for TokenIter<'src> {
         self.lex.next().map(|(res, span)| {
             let span = Span::from(span);
             match res {
-                Ok(k) => Ok(Token::new(k, span)),
-                Err(e) => Err(LexError::new(e, span)),
+                Ok(k) => Ok(Token::new(k, span, self.file)),
+                Err(e) => Err(LexError::new(e, span, self.file)),
             }
         })
     }
@@ -233,3 +378,32 @@ fn command_lex_test() {
         }
     }
 }
+
+#[test]
+fn idents_lexed_through_a_shared_interner_intern_identically() {
+    let interner = SharedInterner::default();
+    let mut a = Kind::lexer_with_extras("stone", interner.clone());
+    let mut b = Kind::lexer_with_extras("stone", interner);
+
+    let Some(Ok(Kind::Ident(a))) = a.next() else {
+        panic!("expected an identifier");
+    };
+    let Some(Ok(Kind::Ident(b))) = b.next() else {
+        panic!("expected an identifier");
+    };
+
+    assert_eq!(a.sym, b.sym);
+    assert_eq!(a.text, "stone");
+}
+
+#[test]
+fn unicode_escape_with_fewer_than_four_hex_digits_is_invalid() {
+    let mut lex = Kind::lexer(r#""\u12""#);
+    assert_eq!(lex.next(), Some(Err(LexErrorItem::InvalidEscape)));
+}
+
+#[test]
+fn unicode_escape_with_four_hex_digits_decodes() {
+    let mut lex = Kind::lexer("\"\\u0041\"");
+    assert_eq!(lex.next(), Some(Ok(Kind::String(Cow::Borrowed("A")))));
+}
@@ -0,0 +1,184 @@
+//! A grouping pass (inspired by ilex's matched-delimiter token trees) that
+//! consumes a flat [`TokenIter`] and pairs up `{}`/`[]` into a tree. Standalone
+//! for now: `ast::Table::parse`, `ast::ExprMap::parse`, and
+//! `ast::StmtCommand::parse` still hand-roll their own brace/bracket tracking
+//! with `extract_token!` and `Separated::parse_terminated` rather than going
+//! through `into_token_trees`, so this doesn't yet replace anything in the
+//! real parse paths.
+
+use std::iter::Peekable;
+
+use crate::{
+    parser::{ParseError, ParseResult},
+    span::{Span, Spanned},
+    token::{Kind, Token, TokenIter},
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenTree<'src> {
+    Leaf(Token<'src>),
+    Group {
+        open: Kind<'src>,
+        close: Kind<'src>,
+        span: Span,
+        children: Vec<TokenTree<'src>>,
+    },
+}
+
+impl<'src> Spanned for TokenTree<'src> {
+    fn span(&self) -> Span {
+        match self {
+            TokenTree::Leaf(token) => token.span(),
+            TokenTree::Group { span, .. } => span.clone(),
+        }
+    }
+}
+
+/// The closing `Kind` a given opening `Kind` must be paired with, or `None`
+/// if `kind` doesn't open a group.
+fn matching_close<'src>(kind: &Kind<'src>) -> Option<Kind<'src>> {
+    match kind {
+        Kind::LeftBrace => Some(Kind::RightBrace),
+        Kind::LeftBracket => Some(Kind::RightBracket),
+        _ => None,
+    }
+}
+
+fn is_close(kind: &Kind) -> bool {
+    matches!(kind, Kind::RightBrace | Kind::RightBracket)
+}
+
+/// Groups every balanced `{}`/`[]` pair in `tokens` into a [`TokenTree`],
+/// consuming the whole stream. A stray closing delimiter left over at the
+/// top level (one with no matching opener) is reported as
+/// [`ParseError::Unexpected`] with an empty expected set, the same as any
+/// other out-of-place token.
+pub fn into_token_trees<'src>(
+    tokens: &mut Peekable<TokenIter<'src>>,
+) -> ParseResult<'src, Vec<TokenTree<'src>>> {
+    let trees = group(tokens)?;
+
+    match tokens.next() {
+        Some(Ok(token)) => Err(ParseError::Unexpected {
+            found: token,
+            expected: crate::parser::ExpectedSet::new(),
+        }),
+        Some(Err(e)) => Err(ParseError::LexError(e)),
+        None => Ok(trees),
+    }
+}
+
+/// Parses a run of sibling trees, stopping (without consuming) as soon as
+/// the next token is a closing delimiter, or the input is exhausted.
+fn group<'src>(tokens: &mut Peekable<TokenIter<'src>>) -> ParseResult<'src, Vec<TokenTree<'src>>> {
+    let mut children = Vec::new();
+
+    loop {
+        match tokens.peek() {
+            Some(Ok(token)) if is_close(&token.kind) => break,
+            Some(Err(err)) => return Err(ParseError::LexError(err.clone())),
+            None => break,
+            Some(Ok(_)) => {}
+        }
+
+        children.push(tree(tokens)?);
+    }
+
+    Ok(children)
+}
+
+/// Parses a single leaf, or an opening delimiter together with everything
+/// up to (and including) its matching close.
+fn tree<'src>(tokens: &mut Peekable<TokenIter<'src>>) -> ParseResult<'src, TokenTree<'src>> {
+    let open = match tokens.next() {
+        Some(Ok(t)) => t,
+        Some(Err(e)) => return Err(ParseError::LexError(e)),
+        None => return Err(ParseError::Eof),
+    };
+
+    let Some(expected) = matching_close(&open.kind) else {
+        return Ok(TokenTree::Leaf(open));
+    };
+
+    let children = group(tokens)?;
+
+    let close = match tokens.next() {
+        Some(Ok(t)) if t.kind == expected => t,
+        Some(Err(e)) => return Err(ParseError::LexError(e)),
+        Some(Ok(_)) | None => {
+            return Err(ParseError::UnbalancedDelimiter {
+                open: open.span.clone(),
+                expected,
+            });
+        }
+    };
+
+    Ok(TokenTree::Group {
+        open: open.kind,
+        close: close.kind,
+        span: Span::new(open.span.start, close.span.end),
+        children,
+    })
+}
+
+#[test]
+fn nested_balanced_delimiters_group_into_a_tree() {
+    use crate::span::FileRef;
+    use logos::Logos;
+
+    let src = "a [b {c}]";
+    let lex = Kind::lexer(src);
+    let mut tokens = TokenIter::new(lex, FileRef(0)).peekable();
+
+    let trees = into_token_trees(&mut tokens).expect("should group");
+
+    let [TokenTree::Leaf(_), TokenTree::Group { open, close, children, .. }] = trees.as_slice()
+    else {
+        panic!("expected a leaf followed by a bracketed group, got {trees:?}");
+    };
+    assert_eq!(*open, Kind::LeftBracket);
+    assert_eq!(*close, Kind::RightBracket);
+    let [TokenTree::Leaf(_), TokenTree::Group { open, close, .. }] = children.as_slice() else {
+        panic!("expected a leaf followed by a braced group, got {children:?}");
+    };
+    assert_eq!(*open, Kind::LeftBrace);
+    assert_eq!(*close, Kind::RightBrace);
+}
+
+#[test]
+fn unclosed_delimiter_reports_unbalanced_delimiter() {
+    use crate::span::FileRef;
+    use logos::Logos;
+
+    let src = "[a";
+    let lex = Kind::lexer(src);
+    let mut tokens = TokenIter::new(lex, FileRef(0)).peekable();
+
+    let err = into_token_trees(&mut tokens).unwrap_err();
+    assert_eq!(
+        err,
+        ParseError::UnbalancedDelimiter {
+            open: Span::new(0, 1),
+            expected: Kind::RightBracket,
+        }
+    );
+}
+
+#[test]
+fn mismatched_closing_delimiter_reports_unbalanced_delimiter() {
+    use crate::span::FileRef;
+    use logos::Logos;
+
+    let src = "[a}";
+    let lex = Kind::lexer(src);
+    let mut tokens = TokenIter::new(lex, FileRef(0)).peekable();
+
+    let err = into_token_trees(&mut tokens).unwrap_err();
+    assert_eq!(
+        err,
+        ParseError::UnbalancedDelimiter {
+            open: Span::new(0, 1),
+            expected: Kind::RightBracket,
+        }
+    );
+}
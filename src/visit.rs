@@ -0,0 +1,281 @@
+//! Shared-reference tree walk over the AST, modeled on syn's `visit` module:
+//! one method per node type, each with a default implementation that
+//! recurses into the node's children via a `walk_*` free function. An
+//! implementor overrides only the nodes it cares about and calls
+//! `visit_*`/`walk_*` to keep recursing into the rest.
+
+use crate::ast::{
+    CoordKind, Expr, ExprBinary, ExprCoord, ExprCoordComponent, ExprMap, ExprMapField,
+    ExprOperator, ExprRange, ExprTarget, ExprUrnary, Function, Ident, Lit, LitBool, LitFloat,
+    LitInt, LitPath, LitString, Stmt, StmtComment, StmtCommand, Table, TableField, UnOp,
+};
+use crate::span::Span;
+
+pub trait Visit<'src> {
+    fn visit_function(&mut self, node: &Function<'src>) {
+        walk_function(self, node);
+    }
+
+    fn visit_stmt(&mut self, node: &Stmt<'src>) {
+        walk_stmt(self, node);
+    }
+
+    fn visit_stmt_command(&mut self, node: &StmtCommand<'src>) {
+        walk_stmt_command(self, node);
+    }
+
+    fn visit_stmt_comment(&mut self, node: &StmtComment<'src>) {
+        walk_stmt_comment(self, node);
+    }
+
+    fn visit_stmt_error(&mut self, node: &Span) {
+        let _ = node;
+    }
+
+    fn visit_expr(&mut self, node: &Expr<'src>) {
+        walk_expr(self, node);
+    }
+
+    fn visit_expr_binary(&mut self, node: &ExprBinary<'src>) {
+        walk_expr_binary(self, node);
+    }
+
+    fn visit_expr_target(&mut self, node: &ExprTarget<'src>) {
+        walk_expr_target(self, node);
+    }
+
+    fn visit_expr_range(&mut self, node: &ExprRange<'src>) {
+        walk_expr_range(self, node);
+    }
+
+    fn visit_expr_urnary(&mut self, node: &ExprUrnary<'src>) {
+        walk_expr_urnary(self, node);
+    }
+
+    fn visit_expr_coord(&mut self, node: &ExprCoord<'src>) {
+        walk_expr_coord(self, node);
+    }
+
+    fn visit_expr_coord_component(&mut self, node: &ExprCoordComponent<'src>) {
+        walk_expr_coord_component(self, node);
+    }
+
+    fn visit_coord_kind(&mut self, node: &CoordKind) {
+        let _ = node;
+    }
+
+    fn visit_expr_map(&mut self, node: &ExprMap<'src>) {
+        walk_expr_map(self, node);
+    }
+
+    fn visit_expr_map_field(&mut self, node: &ExprMapField<'src>) {
+        walk_expr_map_field(self, node);
+    }
+
+    fn visit_expr_operator(&mut self, node: &ExprOperator) {
+        let _ = node;
+    }
+
+    fn visit_un_op(&mut self, node: &UnOp<'src>) {
+        let _ = node;
+    }
+
+    fn visit_table(&mut self, node: &Table<'src, Ident<'src>>) {
+        walk_table(self, node);
+    }
+
+    fn visit_table_field(&mut self, node: &TableField<'src, Ident<'src>>) {
+        walk_table_field(self, node);
+    }
+
+    fn visit_lit(&mut self, node: &Lit<'src>) {
+        walk_lit(self, node);
+    }
+
+    fn visit_lit_int(&mut self, node: &LitInt) {
+        let _ = node;
+    }
+
+    fn visit_lit_float(&mut self, node: &LitFloat) {
+        let _ = node;
+    }
+
+    fn visit_lit_string(&mut self, node: &LitString<'src>) {
+        let _ = node;
+    }
+
+    fn visit_lit_bool(&mut self, node: &LitBool) {
+        let _ = node;
+    }
+
+    fn visit_lit_path(&mut self, node: &LitPath<'src>) {
+        let _ = node;
+    }
+
+    fn visit_ident(&mut self, node: &Ident<'src>) {
+        let _ = node;
+    }
+}
+
+pub fn walk_function<'src, V>(v: &mut V, node: &Function<'src>)
+where
+    V: Visit<'src> + ?Sized,
+{
+    for stmt in &node.statements {
+        v.visit_stmt(stmt);
+    }
+}
+
+pub fn walk_stmt<'src, V>(v: &mut V, node: &Stmt<'src>)
+where
+    V: Visit<'src> + ?Sized,
+{
+    match node {
+        Stmt::Command(c) => v.visit_stmt_command(c),
+        Stmt::Comment(c) => v.visit_stmt_comment(c),
+        Stmt::Error(span) => v.visit_stmt_error(span),
+    }
+}
+
+pub fn walk_stmt_command<'src, V>(v: &mut V, node: &StmtCommand<'src>)
+where
+    V: Visit<'src> + ?Sized,
+{
+    v.visit_ident(&node.ident);
+    if let Some(arguments) = &node.arguments {
+        for arg in arguments {
+            v.visit_expr(arg);
+        }
+    }
+}
+
+pub fn walk_stmt_comment<'src, V>(v: &mut V, node: &StmtComment<'src>)
+where
+    V: Visit<'src> + ?Sized,
+{
+    let _ = (v, node);
+}
+
+pub fn walk_expr<'src, V>(v: &mut V, node: &Expr<'src>)
+where
+    V: Visit<'src> + ?Sized,
+{
+    match node {
+        Expr::Lit(lit) => v.visit_lit(lit),
+        Expr::Urnary(u) => v.visit_expr_urnary(u),
+        Expr::Range(r) => v.visit_expr_range(r),
+        Expr::Map(m) => v.visit_expr_map(m),
+        Expr::Target(t) => v.visit_expr_target(t),
+        Expr::Binary(b) => v.visit_expr_binary(b),
+        Expr::Coord(c) => v.visit_expr_coord(c),
+    }
+}
+
+pub fn walk_expr_binary<'src, V>(v: &mut V, node: &ExprBinary<'src>)
+where
+    V: Visit<'src> + ?Sized,
+{
+    v.visit_expr(&node.lhs);
+    v.visit_expr_operator(&node.op);
+    v.visit_expr(&node.rhs);
+}
+
+pub fn walk_expr_target<'src, V>(v: &mut V, node: &ExprTarget<'src>)
+where
+    V: Visit<'src> + ?Sized,
+{
+    v.visit_ident(&node.target);
+    if let Some(params) = &node.params {
+        v.visit_table(params);
+    }
+}
+
+pub fn walk_expr_range<'src, V>(v: &mut V, node: &ExprRange<'src>)
+where
+    V: Visit<'src> + ?Sized,
+{
+    if let Some(start) = &node.start {
+        v.visit_lit_int(start);
+    }
+    if let Some(end) = &node.end {
+        v.visit_lit_int(end);
+    }
+}
+
+pub fn walk_expr_urnary<'src, V>(v: &mut V, node: &ExprUrnary<'src>)
+where
+    V: Visit<'src> + ?Sized,
+{
+    v.visit_un_op(&node.op);
+    if let Some(expr) = &node.expr {
+        v.visit_expr(expr);
+    }
+}
+
+pub fn walk_expr_coord<'src, V>(v: &mut V, node: &ExprCoord<'src>)
+where
+    V: Visit<'src> + ?Sized,
+{
+    for component in &node.components {
+        v.visit_expr_coord_component(component);
+    }
+}
+
+pub fn walk_expr_coord_component<'src, V>(v: &mut V, node: &ExprCoordComponent<'src>)
+where
+    V: Visit<'src> + ?Sized,
+{
+    v.visit_coord_kind(&node.kind);
+    if let Some(offset) = &node.offset {
+        v.visit_lit(offset);
+    }
+}
+
+pub fn walk_expr_map<'src, V>(v: &mut V, node: &ExprMap<'src>)
+where
+    V: Visit<'src> + ?Sized,
+{
+    for field in &node.fields {
+        v.visit_expr_map_field(field);
+    }
+}
+
+pub fn walk_expr_map_field<'src, V>(v: &mut V, node: &ExprMapField<'src>)
+where
+    V: Visit<'src> + ?Sized,
+{
+    v.visit_lit_string(&node.key);
+    v.visit_expr(&node.value);
+}
+
+pub fn walk_table<'src, V>(v: &mut V, node: &Table<'src, Ident<'src>>)
+where
+    V: Visit<'src> + ?Sized,
+{
+    for field in &node.fields {
+        v.visit_table_field(field);
+    }
+}
+
+pub fn walk_table_field<'src, V>(v: &mut V, node: &TableField<'src, Ident<'src>>)
+where
+    V: Visit<'src> + ?Sized,
+{
+    v.visit_ident(&node.key);
+    if let Some(value) = &node.value {
+        v.visit_expr(value);
+    }
+}
+
+pub fn walk_lit<'src, V>(v: &mut V, node: &Lit<'src>)
+where
+    V: Visit<'src> + ?Sized,
+{
+    match node {
+        Lit::Int(i) => v.visit_lit_int(i),
+        Lit::String(s) => v.visit_lit_string(s),
+        Lit::Bool(b) => v.visit_lit_bool(b),
+        Lit::Float(f) => v.visit_lit_float(f),
+        Lit::Path(p) => v.visit_lit_path(p),
+    }
+}
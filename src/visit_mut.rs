@@ -0,0 +1,280 @@
+//! In-place mutable tree walk, the `&mut` counterpart to [`crate::visit`]:
+//! one method per node type, each recursing into its children via a
+//! `walk_mut_*` free function. Override a node's method to edit it (or its
+//! children) before/after the default recursion runs.
+
+use crate::ast::{
+    CoordKind, Expr, ExprBinary, ExprCoord, ExprCoordComponent, ExprMap, ExprMapField,
+    ExprOperator, ExprRange, ExprTarget, ExprUrnary, Function, Ident, Lit, LitBool, LitFloat,
+    LitInt, LitPath, LitString, Stmt, StmtComment, StmtCommand, Table, TableField, UnOp,
+};
+use crate::span::Span;
+
+pub trait VisitMut<'src> {
+    fn visit_function_mut(&mut self, node: &mut Function<'src>) {
+        walk_function_mut(self, node);
+    }
+
+    fn visit_stmt_mut(&mut self, node: &mut Stmt<'src>) {
+        walk_stmt_mut(self, node);
+    }
+
+    fn visit_stmt_command_mut(&mut self, node: &mut StmtCommand<'src>) {
+        walk_stmt_command_mut(self, node);
+    }
+
+    fn visit_stmt_comment_mut(&mut self, node: &mut StmtComment<'src>) {
+        walk_stmt_comment_mut(self, node);
+    }
+
+    fn visit_stmt_error_mut(&mut self, node: &mut Span) {
+        let _ = node;
+    }
+
+    fn visit_expr_mut(&mut self, node: &mut Expr<'src>) {
+        walk_expr_mut(self, node);
+    }
+
+    fn visit_expr_binary_mut(&mut self, node: &mut ExprBinary<'src>) {
+        walk_expr_binary_mut(self, node);
+    }
+
+    fn visit_expr_target_mut(&mut self, node: &mut ExprTarget<'src>) {
+        walk_expr_target_mut(self, node);
+    }
+
+    fn visit_expr_range_mut(&mut self, node: &mut ExprRange<'src>) {
+        walk_expr_range_mut(self, node);
+    }
+
+    fn visit_expr_urnary_mut(&mut self, node: &mut ExprUrnary<'src>) {
+        walk_expr_urnary_mut(self, node);
+    }
+
+    fn visit_expr_coord_mut(&mut self, node: &mut ExprCoord<'src>) {
+        walk_expr_coord_mut(self, node);
+    }
+
+    fn visit_expr_coord_component_mut(&mut self, node: &mut ExprCoordComponent<'src>) {
+        walk_expr_coord_component_mut(self, node);
+    }
+
+    fn visit_coord_kind_mut(&mut self, node: &mut CoordKind) {
+        let _ = node;
+    }
+
+    fn visit_expr_map_mut(&mut self, node: &mut ExprMap<'src>) {
+        walk_expr_map_mut(self, node);
+    }
+
+    fn visit_expr_map_field_mut(&mut self, node: &mut ExprMapField<'src>) {
+        walk_expr_map_field_mut(self, node);
+    }
+
+    fn visit_expr_operator_mut(&mut self, node: &mut ExprOperator) {
+        let _ = node;
+    }
+
+    fn visit_un_op_mut(&mut self, node: &mut UnOp<'src>) {
+        let _ = node;
+    }
+
+    fn visit_table_mut(&mut self, node: &mut Table<'src, Ident<'src>>) {
+        walk_table_mut(self, node);
+    }
+
+    fn visit_table_field_mut(&mut self, node: &mut TableField<'src, Ident<'src>>) {
+        walk_table_field_mut(self, node);
+    }
+
+    fn visit_lit_mut(&mut self, node: &mut Lit<'src>) {
+        walk_lit_mut(self, node);
+    }
+
+    fn visit_lit_int_mut(&mut self, node: &mut LitInt) {
+        let _ = node;
+    }
+
+    fn visit_lit_float_mut(&mut self, node: &mut LitFloat) {
+        let _ = node;
+    }
+
+    fn visit_lit_string_mut(&mut self, node: &mut LitString<'src>) {
+        let _ = node;
+    }
+
+    fn visit_lit_bool_mut(&mut self, node: &mut LitBool) {
+        let _ = node;
+    }
+
+    fn visit_lit_path_mut(&mut self, node: &mut LitPath<'src>) {
+        let _ = node;
+    }
+
+    fn visit_ident_mut(&mut self, node: &mut Ident<'src>) {
+        let _ = node;
+    }
+}
+
+pub fn walk_function_mut<'src, V>(v: &mut V, node: &mut Function<'src>)
+where
+    V: VisitMut<'src> + ?Sized,
+{
+    for stmt in &mut node.statements {
+        v.visit_stmt_mut(stmt);
+    }
+}
+
+pub fn walk_stmt_mut<'src, V>(v: &mut V, node: &mut Stmt<'src>)
+where
+    V: VisitMut<'src> + ?Sized,
+{
+    match node {
+        Stmt::Command(c) => v.visit_stmt_command_mut(c),
+        Stmt::Comment(c) => v.visit_stmt_comment_mut(c),
+        Stmt::Error(span) => v.visit_stmt_error_mut(span),
+    }
+}
+
+pub fn walk_stmt_command_mut<'src, V>(v: &mut V, node: &mut StmtCommand<'src>)
+where
+    V: VisitMut<'src> + ?Sized,
+{
+    v.visit_ident_mut(&mut node.ident);
+    if let Some(arguments) = &mut node.arguments {
+        for arg in arguments.iter_mut() {
+            v.visit_expr_mut(arg);
+        }
+    }
+}
+
+pub fn walk_stmt_comment_mut<'src, V>(v: &mut V, node: &mut StmtComment<'src>)
+where
+    V: VisitMut<'src> + ?Sized,
+{
+    let _ = (v, node);
+}
+
+pub fn walk_expr_mut<'src, V>(v: &mut V, node: &mut Expr<'src>)
+where
+    V: VisitMut<'src> + ?Sized,
+{
+    match node {
+        Expr::Lit(lit) => v.visit_lit_mut(lit),
+        Expr::Urnary(u) => v.visit_expr_urnary_mut(u),
+        Expr::Range(r) => v.visit_expr_range_mut(r),
+        Expr::Map(m) => v.visit_expr_map_mut(m),
+        Expr::Target(t) => v.visit_expr_target_mut(t),
+        Expr::Binary(b) => v.visit_expr_binary_mut(b),
+        Expr::Coord(c) => v.visit_expr_coord_mut(c),
+    }
+}
+
+pub fn walk_expr_binary_mut<'src, V>(v: &mut V, node: &mut ExprBinary<'src>)
+where
+    V: VisitMut<'src> + ?Sized,
+{
+    v.visit_expr_mut(&mut node.lhs);
+    v.visit_expr_operator_mut(&mut node.op);
+    v.visit_expr_mut(&mut node.rhs);
+}
+
+pub fn walk_expr_target_mut<'src, V>(v: &mut V, node: &mut ExprTarget<'src>)
+where
+    V: VisitMut<'src> + ?Sized,
+{
+    v.visit_ident_mut(&mut node.target);
+    if let Some(params) = &mut node.params {
+        v.visit_table_mut(params);
+    }
+}
+
+pub fn walk_expr_range_mut<'src, V>(v: &mut V, node: &mut ExprRange<'src>)
+where
+    V: VisitMut<'src> + ?Sized,
+{
+    if let Some(start) = &mut node.start {
+        v.visit_lit_int_mut(start);
+    }
+    if let Some(end) = &mut node.end {
+        v.visit_lit_int_mut(end);
+    }
+}
+
+pub fn walk_expr_urnary_mut<'src, V>(v: &mut V, node: &mut ExprUrnary<'src>)
+where
+    V: VisitMut<'src> + ?Sized,
+{
+    v.visit_un_op_mut(&mut node.op);
+    if let Some(expr) = &mut node.expr {
+        v.visit_expr_mut(expr);
+    }
+}
+
+pub fn walk_expr_coord_mut<'src, V>(v: &mut V, node: &mut ExprCoord<'src>)
+where
+    V: VisitMut<'src> + ?Sized,
+{
+    for component in &mut node.components {
+        v.visit_expr_coord_component_mut(component);
+    }
+}
+
+pub fn walk_expr_coord_component_mut<'src, V>(v: &mut V, node: &mut ExprCoordComponent<'src>)
+where
+    V: VisitMut<'src> + ?Sized,
+{
+    v.visit_coord_kind_mut(&mut node.kind);
+    if let Some(offset) = &mut node.offset {
+        v.visit_lit_mut(offset);
+    }
+}
+
+pub fn walk_expr_map_mut<'src, V>(v: &mut V, node: &mut ExprMap<'src>)
+where
+    V: VisitMut<'src> + ?Sized,
+{
+    for field in node.fields.iter_mut() {
+        v.visit_expr_map_field_mut(field);
+    }
+}
+
+pub fn walk_expr_map_field_mut<'src, V>(v: &mut V, node: &mut ExprMapField<'src>)
+where
+    V: VisitMut<'src> + ?Sized,
+{
+    v.visit_lit_string_mut(&mut node.key);
+    v.visit_expr_mut(&mut node.value);
+}
+
+pub fn walk_table_mut<'src, V>(v: &mut V, node: &mut Table<'src, Ident<'src>>)
+where
+    V: VisitMut<'src> + ?Sized,
+{
+    for field in node.fields.iter_mut() {
+        v.visit_table_field_mut(field);
+    }
+}
+
+pub fn walk_table_field_mut<'src, V>(v: &mut V, node: &mut TableField<'src, Ident<'src>>)
+where
+    V: VisitMut<'src> + ?Sized,
+{
+    v.visit_ident_mut(&mut node.key);
+    if let Some(value) = &mut node.value {
+        v.visit_expr_mut(value);
+    }
+}
+
+pub fn walk_lit_mut<'src, V>(v: &mut V, node: &mut Lit<'src>)
+where
+    V: VisitMut<'src> + ?Sized,
+{
+    match node {
+        Lit::Int(i) => v.visit_lit_int_mut(i),
+        Lit::String(s) => v.visit_lit_string_mut(s),
+        Lit::Bool(b) => v.visit_lit_bool_mut(b),
+        Lit::Float(f) => v.visit_lit_float_mut(f),
+        Lit::Path(p) => v.visit_lit_path_mut(p),
+    }
+}